@@ -0,0 +1,112 @@
+//! Write and tag lookup throughput.
+//!
+//! Run with `cargo bench`. `sync_insert` compares storing a feed's articles
+//! one autocommit insert at a time (how sync used to work) with the batched
+//! `Database::add_articles`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use std::collections::HashSet;
+use tagrss::db::{ArticleQuery, Database};
+use tagrss::folder::Expr;
+use tagrss::models::Article;
+use tempfile::TempDir;
+
+const ARTICLES: usize = 200;
+const TAGS: &[&str] = &[
+    "tech/ai/llm",
+    "tech/programming/rust",
+    "science/history",
+    "life/travel",
+    "news/world",
+];
+
+fn articles(source_id: i64, offset: usize, count: usize) -> Vec<Article> {
+    (offset..offset + count)
+        .map(|i| Article {
+            id: 0,
+            source_id,
+            source_title: String::new(),
+            url: format!("http://bench.example/{}", i),
+            title: format!("Article number {}", i),
+            content: Some(format!("<p>Body of article {} with a few words</p>", i)),
+            author: None,
+            published_at: None,
+            word_count: 8,
+            tags: [TAGS[i % TAGS.len()], TAGS[(i / 2) % TAGS.len()]]
+                .iter()
+                .map(|t| t.to_string())
+                .collect(),
+            manual_tags: HashSet::new(),
+            read: i % 3 == 0,
+            starred: false,
+        })
+        .collect()
+}
+
+/// A database file on disk, so commits pay for syncing like a real one
+fn empty_db() -> (TempDir, Database, i64) {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("bench.db")).unwrap();
+    let source = db
+        .add_source("http://bench.example/feed", "Bench", &HashSet::new())
+        .unwrap();
+    (dir, db, source)
+}
+
+fn sync_insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sync_insert");
+    group.throughput(Throughput::Elements(ARTICLES as u64));
+    group.sample_size(10);
+
+    group.bench_function("autocommit", |b| {
+        b.iter_batched(
+            empty_db,
+            |(_dir, db, source)| {
+                for article in articles(source, 0, ARTICLES) {
+                    if !db.article_exists(&article.url).unwrap() {
+                        db.add_article(&article).unwrap();
+                    }
+                }
+            },
+            BatchSize::PerIteration,
+        )
+    });
+
+    group.bench_function("batched", |b| {
+        b.iter_batched(
+            empty_db,
+            |(_dir, db, source)| {
+                db.add_articles(&articles(source, 0, ARTICLES)).unwrap();
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
+fn tag_lookup(c: &mut Criterion) {
+    let (_dir, db, source) = empty_db();
+    db.add_articles(&articles(source, 0, 5000)).unwrap();
+    let filter = Expr::parse("tech AND NOT tech/ai").unwrap();
+
+    let mut group = c.benchmark_group("tag_lookup");
+    group.bench_function("articles_with_tag", |b| {
+        b.iter(|| db.articles_with_tag("tech").unwrap())
+    });
+    group.bench_function("folder_filter", |b| {
+        b.iter(|| {
+            db.count_articles(&ArticleQuery {
+                filter: Some(&filter),
+                ..Default::default()
+            })
+            .unwrap()
+        })
+    });
+    group.bench_function("tag_counts", |b| {
+        b.iter(|| db.tag_counts(&ArticleQuery::default()).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, sync_insert, tag_lookup);
+criterion_main!(benches);
@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::models::Article;
+use crate::{tags, text};
+
+/// A training or evaluation example: tokens and the tags it carries
+#[derive(Debug, Clone)]
+pub struct Example {
+    pub tokens: Vec<String>,
+    pub tags: HashSet<String>,
+}
+
+impl Example {
+    /// Build an example from an article, labelled with the tags a person chose
+    /// for it: its source's tags and its manual tags (plus their ancestors),
+    /// minus the tags removed from it by hand (`ManualTags::removed`).
+    /// Rule-added tags are left out, since rules already decide those.
+    pub fn from_article(
+        article: &Article,
+        source_tags: &HashSet<String>,
+        removed: &HashSet<String>,
+    ) -> Self {
+        let chosen: HashSet<String> = source_tags
+            .union(&article.manual_tags)
+            .filter(|t| !removed.contains(*t))
+            .cloned()
+            .collect();
+        Example {
+            tokens: text::article_tokens(&article.title, article.content.as_deref()),
+            tags: tags::with_ancestors(&chosen),
+        }
+    }
+}
+
+/// Token statistics for the documents carrying one tag
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TagModel {
+    pub docs: u32,
+    pub tokens: u64,
+    pub token_counts: HashMap<String, u32>,
+}
+
+/// Multinomial Naive Bayes, one-vs-rest per tag.
+///
+/// Only positive counts are stored per tag; counts for "not tag" are the
+/// corpus totals minus the tag's counts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NaiveBayes {
+    pub docs: u32,
+    pub tokens: u64,
+    pub token_counts: HashMap<String, u32>,
+    pub tags: HashMap<String, TagModel>,
+}
+
+impl NaiveBayes {
+    /// Train on examples, keeping only tags seen on at least `min_docs` documents
+    pub fn train(examples: &[Example], min_docs: u32) -> Self {
+        let mut model = NaiveBayes::default();
+        for example in examples {
+            model.docs += 1;
+            model.tokens += example.tokens.len() as u64;
+            for token in &example.tokens {
+                *model.token_counts.entry(token.clone()).or_default() += 1;
+            }
+            for tag in &example.tags {
+                let tm = model.tags.entry(tag.clone()).or_default();
+                tm.docs += 1;
+                tm.tokens += example.tokens.len() as u64;
+                for token in &example.tokens {
+                    *tm.token_counts.entry(token.clone()).or_default() += 1;
+                }
+            }
+        }
+        model.tags.retain(|_, tm| tm.docs >= min_docs);
+        model
+    }
+
+    /// Probability in [0, 1] that a document with these tokens carries `tag`.
+    /// Returns None if the tag is unknown to the model.
+    pub fn confidence(&self, tokens: &[String], tag: &str) -> Option<f64> {
+        let tm = self.tags.get(tag)?;
+        let vocab = self.token_counts.len() as f64;
+        let neg_docs = self.docs - tm.docs;
+        let neg_tokens = (self.tokens - tm.tokens) as f64;
+
+        // Laplace-smoothed log odds
+        let mut log_odds = ((tm.docs as f64 + 1.0) / (neg_docs as f64 + 1.0)).ln();
+        for token in tokens {
+            let Some(&total) = self.token_counts.get(token) else {
+                continue;
+            };
+            let pos = tm.token_counts.get(token).copied().unwrap_or(0);
+            let p_pos = (pos as f64 + 1.0) / (tm.tokens as f64 + vocab);
+            let p_neg = ((total - pos) as f64 + 1.0) / (neg_tokens + vocab);
+            log_odds += (p_pos / p_neg).ln();
+        }
+        Some(1.0 / (1.0 + (-log_odds).exp()))
+    }
+
+    /// All tags predicted with at least `threshold` confidence, most confident first
+    pub fn predict(&self, tokens: &[String], threshold: f64) -> Vec<(String, f64)> {
+        let mut predicted: Vec<_> = self
+            .tags
+            .keys()
+            .filter_map(|tag| {
+                let c = self.confidence(tokens, tag)?;
+                (c >= threshold).then(|| (tag.clone(), c))
+            })
+            .collect();
+        predicted.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        predicted
+    }
+}
+
+/// Precision/recall counts for one tag
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TagScore {
+    pub tag: String,
+    pub true_pos: u32,
+    pub false_pos: u32,
+    pub false_neg: u32,
+}
+
+impl TagScore {
+    pub fn precision(&self) -> f64 {
+        ratio(self.true_pos, self.true_pos + self.false_pos)
+    }
+
+    pub fn recall(&self) -> f64 {
+        ratio(self.true_pos, self.true_pos + self.false_neg)
+    }
+
+    /// Number of test documents that actually carry the tag
+    pub fn support(&self) -> u32 {
+        self.true_pos + self.false_neg
+    }
+}
+
+fn ratio(num: u32, den: u32) -> f64 {
+    if den == 0 {
+        0.0
+    } else {
+        num as f64 / den as f64
+    }
+}
+
+/// Score a model against held-out examples, one entry per tag the model knows
+pub fn evaluate(model: &NaiveBayes, test: &[Example], threshold: f64) -> Vec<TagScore> {
+    let mut scores: HashMap<&str, TagScore> = model
+        .tags
+        .keys()
+        .map(|tag| {
+            let score = TagScore {
+                tag: tag.clone(),
+                ..Default::default()
+            };
+            (tag.as_str(), score)
+        })
+        .collect();
+
+    for example in test {
+        for (tag, score) in scores.iter_mut() {
+            let predicted = model
+                .confidence(&example.tokens, tag)
+                .is_some_and(|c| c >= threshold);
+            match (predicted, example.tags.contains(*tag)) {
+                (true, true) => score.true_pos += 1,
+                (true, false) => score.false_pos += 1,
+                (false, true) => score.false_neg += 1,
+                (false, false) => {}
+            }
+        }
+    }
+
+    let mut scores: Vec<_> = scores.into_values().collect();
+    scores.sort_by(|a, b| a.tag.cmp(&b.tag));
+    scores
+}
@@ -1,13 +1,17 @@
 use anyhow::{Context, Result};
 use quick_xml::events::{BytesStart, Event};
 use quick_xml::Reader;
+use regex::Regex;
 use serde::Deserialize;
 use std::collections::HashSet;
+use std::fmt;
 use std::fs;
 use std::path::Path;
 
-use crate::folder::{Expr, Folder};
-use crate::models::Rule;
+use crate::folder::{self, Expr, Folder, FolderView, Mute, ParseError};
+use crate::models::{default_threshold, Rule};
+use crate::retention::{Policy, Retention};
+use crate::tags::{self, TagEdit};
 
 /// A feed entry parsed from OPML
 #[derive(Debug)]
@@ -57,11 +61,7 @@ fn parse_outline(e: &BytesStart) -> Option<OpmlFeed> {
 
         match key {
             "type" => feed_type = Some(value),
-            "text" | "title" => {
-                if text.is_none() {
-                    text = Some(value);
-                }
-            }
+            "text" | "title" if text.is_none() => text = Some(value),
             "xmlUrl" => xml_url = Some(value),
             "htmlUrl" => html_url = Some(value),
             "tags" => tags_str = Some(value),
@@ -124,6 +124,12 @@ pub enum RuleConfig {
         min_days: Option<u32>,
         tag: String,
     },
+    #[serde(rename = "classifier")]
+    Classifier {
+        tag: String,
+        #[serde(default = "default_threshold")]
+        threshold: f64,
+    },
 }
 
 impl From<RuleConfig> for Rule {
@@ -134,6 +140,7 @@ impl From<RuleConfig> for Rule {
                 Rule::Contains { pattern, case_sensitive, tag }
             }
             RuleConfig::Age { max_days, min_days, tag } => Rule::Age { max_days, min_days, tag },
+            RuleConfig::Classifier { tag, threshold } => Rule::Classifier { tag, threshold },
         }
     }
 }
@@ -152,16 +159,60 @@ pub fn load_rules(path: impl AsRef<Path>) -> Result<Vec<Rule>> {
 /// YAML structure for folders file
 #[derive(Debug, Deserialize)]
 pub struct FoldersConfig {
+    #[serde(default)]
     pub folders: Vec<FolderConfig>,
+    #[serde(default)]
+    pub mute: Vec<MuteConfig>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct FolderConfig {
     pub name: String,
     pub filter: String,
+    /// Optional `sort`, `unread`, `limit` and `group` defaults
+    #[serde(flatten)]
+    pub view: FolderView,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MuteConfig {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub filter: String,
+    #[serde(default)]
+    pub mark_read: bool,
+}
+
+/// A folder whose filter failed to parse
+#[derive(Debug)]
+pub struct FolderError {
+    pub name: String,
+    pub filter: String,
+    pub error: ParseError,
+}
+
+/// All folder filter errors in a folders file, reported together
+#[derive(Debug)]
+pub struct FolderErrors {
+    pub errors: Vec<FolderError>,
 }
 
-/// Load folders from YAML file
+impl fmt::Display for FolderErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} folder filter(s) failed to parse", self.errors.len())?;
+        for e in &self.errors {
+            write!(f, "\n\nfolder '{}': {}", e.name, e.error.render(&e.filter))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for FolderErrors {}
+
+/// Load folders from YAML file.
+///
+/// Every filter is parsed before giving up, so one run reports all bad folders
+/// (as `FolderErrors`).
 pub fn load_folders(path: impl AsRef<Path>) -> Result<Vec<Folder>> {
     let content = fs::read_to_string(path.as_ref())
         .with_context(|| format!("Failed to read folders file: {:?}", path.as_ref()))?;
@@ -170,14 +221,516 @@ pub fn load_folders(path: impl AsRef<Path>) -> Result<Vec<Folder>> {
         .with_context(|| "Failed to parse folders YAML")?;
 
     let mut folders = Vec::new();
+    let mut errors = Vec::new();
     for fc in config.folders {
-        let filter = Expr::parse(&fc.filter)
-            .map_err(|e| anyhow::anyhow!("Failed to parse filter for '{}': {}", fc.name, e))?;
-        folders.push(Folder {
-            name: fc.name,
-            filter,
-        });
+        match Expr::parse(&fc.filter) {
+            Ok(filter) => folders.push(Folder {
+                name: fc.name,
+                filter,
+                view: fc.view,
+            }),
+            Err(error) => errors.push(FolderError {
+                name: fc.name,
+                filter: fc.filter,
+                error,
+            }),
+        }
     }
 
+    if !errors.is_empty() {
+        return Err(FolderErrors { errors }.into());
+    }
     Ok(folders)
 }
+
+/// Load the `mute:` list from the folders YAML file.
+///
+/// Unnamed mutes are called "mute N" (1-based) in messages.
+pub fn load_mutes(path: impl AsRef<Path>) -> Result<Vec<Mute>> {
+    let content = fs::read_to_string(path.as_ref())
+        .with_context(|| format!("Failed to read folders file: {:?}", path.as_ref()))?;
+
+    let config: FoldersConfig =
+        serde_yaml::from_str(&content).with_context(|| "Failed to parse folders YAML")?;
+
+    config
+        .mute
+        .into_iter()
+        .enumerate()
+        .map(|(i, mc)| {
+            let name = mc.name.unwrap_or_else(|| format!("mute {}", i + 1));
+            let filter = Expr::parse(&mc.filter)
+                .map_err(|e| anyhow::anyhow!("mute '{}': {}", name, e.render(&mc.filter)))?;
+            Ok(Mute {
+                name,
+                filter,
+                mark_read: mc.mark_read,
+            })
+        })
+        .collect()
+}
+
+/// YAML structure for retention file
+#[derive(Debug, Deserialize)]
+pub struct RetentionConfig {
+    #[serde(default)]
+    pub policies: Vec<PolicyConfig>,
+    #[serde(default)]
+    pub keep: Option<String>,
+    #[serde(default)]
+    pub prune_after_sync: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PolicyConfig {
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Defaults to every article
+    #[serde(default)]
+    pub filter: Option<String>,
+    pub max_days: Option<u32>,
+    pub max_per_source: Option<u32>,
+}
+
+/// Load retention policies from YAML file.
+///
+/// Unnamed policies are called "policy N" (1-based) in messages.
+pub fn load_retention(path: impl AsRef<Path>) -> Result<Retention> {
+    let content = fs::read_to_string(path.as_ref())
+        .with_context(|| format!("Failed to read retention file: {:?}", path.as_ref()))?;
+
+    let config: RetentionConfig =
+        serde_yaml::from_str(&content).with_context(|| "Failed to parse retention YAML")?;
+
+    let policies = config
+        .policies
+        .into_iter()
+        .enumerate()
+        .map(|(i, pc)| {
+            let name = pc.name.unwrap_or_else(|| format!("policy {}", i + 1));
+            if pc.max_days.is_none() && pc.max_per_source.is_none() {
+                anyhow::bail!("policy '{}': set max_days or max_per_source", name);
+            }
+            let filter = match &pc.filter {
+                Some(filter) => Expr::parse(filter)
+                    .map_err(|e| anyhow::anyhow!("policy '{}': {}", name, e.render(filter)))?,
+                None => Expr::ALWAYS,
+            };
+            Ok(Policy {
+                name,
+                filter,
+                max_days: pc.max_days,
+                max_per_source: pc.max_per_source,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    let keep = config
+        .keep
+        .map(|keep| Expr::parse(&keep).map_err(|e| anyhow::anyhow!("keep: {}", e.render(&keep))))
+        .transpose()?;
+
+    Ok(Retention {
+        policies,
+        keep,
+        prune_after_sync: config.prune_after_sync,
+    })
+}
+
+/// Rewrite renamed tags in the `tag:` values of a rules YAML file.
+///
+/// Edits are done line by line so comments and layout survive.
+/// Rules whose tag would be deleted are left untouched.
+/// Returns the number of rules changed.
+pub fn rewrite_rules_file(path: impl AsRef<Path>, edits: &[TagEdit]) -> Result<usize> {
+    rewrite_yaml_values(path.as_ref(), "tag", |value| {
+        rewrite_tag_keeping_deleted(edits, value)
+    })
+}
+
+/// Rewrite renamed tags inside the `filter:` values of a folders YAML file.
+///
+/// Same line-by-line approach as `rewrite_rules_file`; deleted tags are left in place.
+/// Returns the number of folders changed.
+pub fn rewrite_folders_file(path: impl AsRef<Path>, edits: &[TagEdit]) -> Result<usize> {
+    rewrite_yaml_values(path.as_ref(), "filter", |value| {
+        folder::rewrite_filter_tags(value, |tag| rewrite_tag_keeping_deleted(edits, tag))
+    })
+}
+
+fn rewrite_tag_keeping_deleted(edits: &[TagEdit], tag: &str) -> String {
+    edits
+        .iter()
+        .try_fold(tag.to_string(), |t, edit| edit.rewrite(&t))
+        .unwrap_or_else(|| tag.to_string())
+}
+
+/// Replace the `key:` values of a YAML file with `rewrite(value)`.
+///
+/// Values are decoded before rewriting and re-encoded in the same quoting
+/// style (`yaml_scalar` for plain values), so quotes in the new value cannot
+/// break the file. The result is parsed back and every `key:` value compared
+/// with the expected one before it is written.
+fn rewrite_yaml_values(path: &Path, key: &str, rewrite: impl Fn(&str) -> String) -> Result<usize> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file: {:?}", path))?;
+    let re = Regex::new(&format!(r"^(\s*(?:-\s+)?{}:\s*)(.*)$", regex::escape(key)))?;
+
+    let mut changed = 0;
+    let mut out = String::with_capacity(content.len());
+    for line in content.split_inclusive('\n') {
+        let body = line.trim_end_matches(['\r', '\n']);
+        let ending = &line[body.len()..];
+        let Some(caps) = re.captures(body) else {
+            out.push_str(line);
+            continue;
+        };
+
+        // Split off a trailing comment, then decode the scalar
+        let rest = &caps[2];
+        let (value, comment) = match rest.find(" #") {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, ""),
+        };
+        let trimmed = value.trim_end();
+        let Ok(old) = serde_yaml::from_str::<String>(trimmed) else {
+            out.push_str(line);
+            continue;
+        };
+
+        let new = rewrite(&old);
+        if new == old {
+            out.push_str(line);
+            continue;
+        }
+        changed += 1;
+        out.push_str(&caps[1]);
+        // Keep the value's quoting style; a JSON string is valid double-quoted YAML
+        out.push_str(&match trimmed.chars().next() {
+            Some('"') => serde_json::to_string(&new)?,
+            Some('\'') => format!("'{}'", new.replace('\'', "''")),
+            _ => yaml_scalar(&new),
+        });
+        out.push_str(&value[trimmed.len()..]);
+        out.push_str(comment);
+        out.push_str(ending);
+    }
+    if changed == 0 {
+        return Ok(0);
+    }
+
+    let parse = |text: &str| serde_yaml::from_str::<serde_yaml::Value>(text);
+    let mut expected = Vec::new();
+    key_values(&parse(&content)?, key, &mut expected);
+    let expected: Vec<String> = expected.iter().map(|v| rewrite(v)).collect();
+    let mut written = Vec::new();
+    key_values(
+        &parse(&out)
+            .with_context(|| format!("Editing {:?} would break it; edit it by hand", path))?,
+        key,
+        &mut written,
+    );
+    if written != expected {
+        anyhow::bail!("Could not safely edit {:?}; edit it by hand", path);
+    }
+    fs::write(path, out).with_context(|| format!("Failed to write {:?}", path))?;
+    Ok(changed)
+}
+
+/// Every string value stored under `key` in a YAML document, in document order
+fn key_values(value: &serde_yaml::Value, key: &str, out: &mut Vec<String>) {
+    match value {
+        serde_yaml::Value::Mapping(map) => {
+            for (k, v) in map {
+                match (k.as_str(), v.as_str()) {
+                    (Some(k), Some(v)) if k == key => out.push(v.to_string()),
+                    _ => key_values(v, key, out),
+                }
+            }
+        }
+        serde_yaml::Value::Sequence(items) => {
+            for item in items {
+                key_values(item, key, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// A change to the folder list of a folders YAML file
+#[derive(Debug, Clone, PartialEq)]
+pub enum FolderEdit {
+    /// Append a new folder
+    Add { name: String, filter: String },
+    /// Replace a folder's filter
+    SetFilter { name: String, filter: String },
+    /// Rename a folder, moving its nested folders along: Tech -> Technology
+    /// turns Tech/AI into Technology/AI
+    Rename { from: String, to: String },
+    /// Move a folder to a 0-based position in the list
+    Move { name: String, position: usize },
+    /// Delete a folder and all folders nested under it
+    Delete { name: String },
+}
+
+/// Apply a folder edit to a folders YAML file, creating the file if needed.
+///
+/// Like `rewrite_folders_file`, the file is edited as text so comments and
+/// layout survive: each folder is the block of lines from its `- ` item
+/// (plus any comment lines right above it) to the next folder. The result is
+/// parsed back and compared with the expected folders before it is written,
+/// so a layout this cannot handle is an error instead of a corrupted file.
+pub fn edit_folders_file(path: impl AsRef<Path>, edit: &FolderEdit) -> Result<()> {
+    let path = path.as_ref();
+    let content = if path.exists() {
+        fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?
+    } else {
+        "folders:\n".to_string()
+    };
+    let config: FoldersConfig =
+        serde_yaml::from_str(&content).with_context(|| "Failed to parse folders YAML")?;
+    let mut expected: Vec<(String, String)> = config
+        .folders
+        .into_iter()
+        .map(|f| (f.name, f.filter))
+        .collect();
+    let mut doc = FoldersDoc::parse(&content)?;
+    if doc.entries.len() != expected.len() {
+        anyhow::bail!("Could not find every folder in {:?}; edit it by hand", path);
+    }
+
+    let find = |folders: &[(String, String)], name: &str| {
+        folders
+            .iter()
+            .position(|(n, _)| n == name)
+            .ok_or_else(|| anyhow::anyhow!("No folder named '{}'", name))
+    };
+    let check_new_name = |folders: &[(String, String)], name: &str| {
+        if name.trim().is_empty() {
+            anyhow::bail!("Folder name cannot be empty");
+        }
+        if folders.iter().any(|(n, _)| n == name) {
+            anyhow::bail!("Folder '{}' already exists", name);
+        }
+        Ok(())
+    };
+    match edit {
+        FolderEdit::Add { name, filter } => {
+            check_new_name(&expected, name)?;
+            Expr::parse(filter)?;
+            doc.push(name, filter);
+            expected.push((name.clone(), filter.clone()));
+        }
+        FolderEdit::SetFilter { name, filter } => {
+            let i = find(&expected, name)?;
+            Expr::parse(filter)?;
+            doc.set_value(i, "filter", filter)?;
+            expected[i].1 = filter.clone();
+        }
+        FolderEdit::Rename { from, to } => {
+            let subtree = find_subtree(&expected, from)?;
+            check_new_name(&expected, to)?;
+            for i in subtree {
+                let name = format!("{}{}", to, &expected[i].0[from.len()..]);
+                if expected.iter().any(|(n, _)| *n == name) {
+                    anyhow::bail!("Folder '{}' already exists", name);
+                }
+                doc.set_value(i, "name", &name)?;
+                expected[i].0 = name;
+            }
+        }
+        FolderEdit::Move { name, position } => {
+            let i = find(&expected, name)?;
+            let position = (*position).min(expected.len() - 1);
+            let entry = doc.entries.remove(i);
+            doc.entries.insert(position, entry);
+            let folder = expected.remove(i);
+            expected.insert(position, folder);
+        }
+        FolderEdit::Delete { name } => {
+            for i in find_subtree(&expected, name)?.into_iter().rev() {
+                doc.entries.remove(i);
+                expected.remove(i);
+            }
+        }
+    }
+
+    let out = doc.render();
+    let written: FoldersConfig = serde_yaml::from_str(&out)
+        .with_context(|| format!("Editing {:?} would break it; edit it by hand", path))?;
+    let written: Vec<(String, String)> = written
+        .folders
+        .into_iter()
+        .map(|f| (f.name, f.filter))
+        .collect();
+    if written != expected {
+        anyhow::bail!("Could not safely edit {:?}; edit it by hand", path);
+    }
+    fs::write(path, out).with_context(|| format!("Failed to write {:?}", path))
+}
+
+/// Positions of a folder and all folders nested under it
+fn find_subtree(folders: &[(String, String)], name: &str) -> Result<Vec<usize>> {
+    let found: Vec<usize> = folders
+        .iter()
+        .enumerate()
+        .filter(|(_, (n, _))| tags::is_under(n, name))
+        .map(|(i, _)| i)
+        .collect();
+    if found.is_empty() {
+        anyhow::bail!("No folder named '{}'", name);
+    }
+    Ok(found)
+}
+
+/// A folders YAML file split into the folder list items and everything around them
+struct FoldersDoc {
+    head: String,
+    /// Lines of each folder item, without trailing blank lines
+    entries: Vec<String>,
+    /// Blank lines put between items
+    separator: String,
+    tail: String,
+    indent: String,
+}
+
+impl FoldersDoc {
+    fn parse(content: &str) -> Result<Self> {
+        let lines: Vec<&str> = content.split_inclusive('\n').collect();
+        let blank = |l: &str| l.trim().is_empty();
+        let comment = |l: &str| l.trim_start().starts_with('#');
+        let indent_of = |l: &str| l.len() - l.trim_start().len();
+
+        let Some(key) = lines.iter().position(|l| l.starts_with("folders:")) else {
+            anyhow::bail!("No 'folders:' key in folders YAML");
+        };
+        let item = |l: &str| {
+            let t = l.trim_start();
+            t == "-" || t.starts_with("- ") || t.starts_with("-\n")
+        };
+        let first = lines[key + 1..]
+            .iter()
+            .position(|l| item(l))
+            .map(|i| key + 1 + i);
+        let indent = first.map_or("  ".to_string(), |i| {
+            lines[i][..indent_of(lines[i])].to_string()
+        });
+
+        // Item starts, and where the list ends (a line back at the key's level)
+        let mut starts = Vec::new();
+        let mut end = lines.len();
+        if let Some(first) = first {
+            for (i, l) in lines.iter().enumerate().skip(first) {
+                if blank(l) || comment(l) {
+                    continue;
+                }
+                if indent_of(l) == indent.len() && item(l) {
+                    starts.push(i);
+                } else if indent_of(l) < indent.len() || (indent_of(l) == indent.len() && !item(l))
+                {
+                    end = i;
+                    break;
+                }
+            }
+        }
+        // Comment lines directly above an item belong to it
+        let attach = |mut i: usize| {
+            while i > 0 && comment(lines[i - 1]) {
+                i -= 1;
+            }
+            i
+        };
+        let block_starts: Vec<usize> = starts.iter().map(|&i| attach(i)).collect();
+        // Blank and comment lines after the last item stay at the end of the file
+        let mut list_end = end;
+        while list_end > starts.last().map_or(0, |&s| s + 1)
+            && (blank(lines[list_end - 1]) || comment(lines[list_end - 1]))
+        {
+            list_end -= 1;
+        }
+
+        let head_end = block_starts
+            .first()
+            .copied()
+            .unwrap_or(list_end.max(key + 1));
+        let mut entries = Vec::new();
+        let mut separator = None;
+        for (n, &start) in block_starts.iter().enumerate() {
+            let stop = block_starts.get(n + 1).copied().unwrap_or(list_end);
+            let block = lines[start..stop].concat();
+            let body = block.trim_end();
+            // Keep the spacing found between the first two items
+            if n + 1 < block_starts.len() && separator.is_none() {
+                let blank_lines = block[body.len()..].matches('\n').count();
+                separator = Some("\n".repeat(blank_lines.saturating_sub(1)));
+            }
+            entries.push(format!("{}\n", body));
+        }
+
+        Ok(FoldersDoc {
+            head: lines[..head_end].concat(),
+            entries,
+            separator: separator.unwrap_or_else(|| "\n".to_string()),
+            tail: lines[list_end.max(head_end)..].concat(),
+            indent,
+        })
+    }
+
+    fn push(&mut self, name: &str, filter: &str) {
+        let pad = " ".repeat(self.indent.len() + 2);
+        self.entries.push(format!(
+            "{}- name: {}\n{}filter: {}\n",
+            self.indent,
+            yaml_scalar(name),
+            pad,
+            yaml_scalar(filter)
+        ));
+    }
+
+    /// Replace the value of `key` in one item, keeping any trailing comment
+    fn set_value(&mut self, index: usize, key: &str, value: &str) -> Result<()> {
+        let re = Regex::new(&format!(
+            r"^(\s*(?:-\s+)?{}:[ \t]*)(.*)$",
+            regex::escape(key)
+        ))?;
+        let entry = &mut self.entries[index];
+        let mut out = String::with_capacity(entry.len());
+        let mut found = false;
+        for line in entry.split_inclusive('\n') {
+            let body = line.trim_end_matches(['\r', '\n']);
+            match re.captures(body) {
+                Some(caps) if !found => {
+                    found = true;
+                    let comment = caps[2].find(" #").map_or("", |i| &caps[2][i..]);
+                    out.push_str(&caps[1]);
+                    out.push_str(&yaml_scalar(value));
+                    out.push_str(comment);
+                    out.push_str(&line[body.len()..]);
+                }
+                _ => out.push_str(line),
+            }
+        }
+        if !found {
+            anyhow::bail!("Could not find '{}:' of folder #{}", key, index + 1);
+        }
+        *entry = out;
+        Ok(())
+    }
+
+    fn render(&self) -> String {
+        let mut out = self.head.clone();
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str(&self.entries.join(&self.separator));
+        out.push_str(&self.tail);
+        out
+    }
+}
+
+/// A string as a single-line YAML scalar, quoted only when needed
+fn yaml_scalar(value: &str) -> String {
+    serde_yaml::to_string(value)
+        .map(|s| s.trim_end().to_string())
+        .unwrap_or_else(|_| format!("{:?}", value))
+}
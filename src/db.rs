@@ -1,59 +1,404 @@
-use anyhow::Result;
-use chrono::{DateTime, Utc};
-use rusqlite::{params, Connection};
-use std::collections::HashSet;
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::functions::FunctionFlags;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::path::Path;
 
-use crate::models::{Article, Rule, Source};
+use crate::classify::NaiveBayes;
+use crate::folder::{self, ArticleCounts, Expr, Folder, FolderCount, FolderNode, SortOrder};
+use crate::migrations::{self, Migration, MIGRATIONS};
+use crate::models::{Article, ManualTags, Rule, Source};
+use crate::retention::{Policy, Retention};
+use crate::tags::{self, TagCount, TagEdit, TagEditReport};
+use crate::text;
+
+const ARTICLE_COLUMNS: &str = r#"id, IFNULL(source_id, 0), url, title, content, published_at, word_count, tags, read,
+    (SELECT json_group_array(tag) FROM manual_tags m
+     WHERE m.article_id = articles.id AND m.removed = 0),
+    IFNULL((SELECT title FROM sources s WHERE s.id = articles.source_id), ''), author, starred"#;
+
+fn article_from_row(row: &Row) -> rusqlite::Result<Article> {
+    let tags_json: String = row.get(7)?;
+    let published: Option<String> = row.get(5)?;
+    let read_int: i32 = row.get(8)?;
+    let manual_json: String = row.get(9)?;
+    Ok(Article {
+        id: row.get(0)?,
+        source_id: row.get(1)?,
+        source_title: row.get(10)?,
+        url: row.get(2)?,
+        title: row.get(3)?,
+        content: row.get(4)?,
+        author: row.get(11)?,
+        published_at: published
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc)),
+        word_count: row.get(6)?,
+        tags: serde_json::from_str(&tags_json).unwrap_or_default(),
+        manual_tags: serde_json::from_str(&manual_json).unwrap_or_default(),
+        read: read_int != 0,
+        starred: row.get(12)?,
+    })
+}
+
+const SOURCE_COLUMNS: &str = "id, url, title, tags, last_updated, enabled";
+
+fn source_from_row(row: &Row) -> rusqlite::Result<Source> {
+    let tags_json: String = row.get(3)?;
+    let last_updated: Option<String> = row.get(4)?;
+    Ok(Source {
+        id: row.get(0)?,
+        url: row.get(1)?,
+        title: row.get(2)?,
+        tags: serde_json::from_str(&tags_json).unwrap_or_default(),
+        last_updated: parse_date(last_updated),
+        enabled: row.get(5)?,
+    })
+}
+
+fn parse_date(value: Option<String>) -> Option<DateTime<Utc>> {
+    value
+        .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Which articles `Database::query_articles` returns
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArticleQuery<'a> {
+    pub filter: Option<&'a Expr>,
+    /// Leave out articles matching this filter (see `folder::mute_filter`)
+    pub mute: Option<&'a Expr>,
+    /// Only articles from this source
+    pub source: Option<i64>,
+    pub unread: bool,
+    pub limit: Option<usize>,
+    pub sort: SortOrder,
+}
+
+impl ArticleQuery<'_> {
+    /// The WHERE condition selecting the query's articles, and its parameters
+    fn condition(&self) -> (String, Vec<Value>) {
+        let mut condition = "1".to_string();
+        let mut params = Vec::new();
+        if let Some(filter) = self.filter {
+            let compiled = filter.to_sql();
+            condition = format!("({})", compiled.clause);
+            params = compiled.params;
+        }
+        if let Some(mute) = self.mute {
+            let compiled = mute.to_sql();
+            condition.push_str(&format!(" AND NOT ({})", compiled.clause));
+            params.extend(compiled.params);
+        }
+        if let Some(source) = self.source {
+            condition.push_str(" AND articles.source_id = ?");
+            params.push(Value::Integer(source));
+        }
+        if self.unread {
+            condition.push_str(" AND articles.read = 0");
+        }
+        (condition, params)
+    }
+
+    /// The ORDER BY terms for the query's sort order, and their parameters
+    fn order(&self) -> (String, Vec<Value>) {
+        const NEWEST: &str = "published_at DESC";
+        match self.sort {
+            SortOrder::Newest => (NEWEST.to_string(), Vec::new()),
+            SortOrder::Oldest => ("published_at IS NULL, published_at ASC".to_string(), Vec::new()),
+            SortOrder::Shortest => (format!("word_count ASC, {}", NEWEST), Vec::new()),
+            SortOrder::Source => (
+                format!(
+                    "(SELECT title FROM sources s WHERE s.id = articles.source_id) COLLATE NOCASE, {}",
+                    NEWEST
+                ),
+                Vec::new(),
+            ),
+            SortOrder::Score => match self.filter.and_then(Expr::score_sql) {
+                Some(score) => (format!("{} DESC, {}", score.clause, NEWEST), score.params),
+                None => (NEWEST.to_string(), Vec::new()),
+            },
+        }
+    }
+}
+
+/// When an article was last new to the reader: the later of its fetch and
+/// publication times, in seconds
+const ARTICLE_AGE_BASE: &str =
+    "MAX(unixepoch(articles.created_at), IFNULL(unixepoch(articles.published_at), 0))";
+
+/// The condition selecting the articles a retention policy deletes, and its
+/// parameters. `kept` is the condition for articles not protected by `keep`.
+fn policy_condition(policy: &Policy, kept: &(String, Vec<Value>)) -> (String, Vec<Value>) {
+    let filter = policy.filter.to_sql();
+    let scope = format!("({}) AND {}", filter.clause, kept.0);
+    let mut scope_params = filter.params;
+    scope_params.extend(kept.1.iter().cloned());
+
+    let mut limits = Vec::new();
+    let mut params = scope_params.clone();
+    if let Some(days) = policy.max_days {
+        limits.push(format!("{} < unixepoch('now') - ?", ARTICLE_AGE_BASE));
+        params.push(Value::Integer(days as i64 * 86_400));
+    }
+    if let Some(max) = policy.max_per_source {
+        limits.push(format!(
+            "articles.id IN (SELECT id FROM (
+                 SELECT id, ROW_NUMBER() OVER (
+                     PARTITION BY source_id
+                     ORDER BY IFNULL(unixepoch(published_at), unixepoch(created_at)) DESC, id DESC
+                 ) AS n
+                 FROM articles WHERE {}
+             ) WHERE n > ?)",
+            scope
+        ));
+        params.extend(scope_params);
+        params.push(Value::Integer(max as i64));
+    }
+    if limits.is_empty() {
+        return ("0".to_string(), Vec::new());
+    }
+    (format!("{} AND ({})", scope, limits.join(" OR ")), params)
+}
+
+/// An article `Database::prune` would delete, and the first policy deleting it
+#[derive(Debug, Clone)]
+pub struct PruneCandidate {
+    pub article: Article,
+    pub policy: String,
+}
+
+/// How many mark-read actions `Database::undo_read` can go back
+const UNDO_DEPTH: i64 = 50;
+
+/// Current time as stored in read and opened timestamps. Fixed-width, so the
+/// text sorts in time order.
+fn now_timestamp() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// A mark-read action recorded for undo
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadAction {
+    pub id: i64,
+    pub description: String,
+    pub at: Option<DateTime<Utc>>,
+    /// Articles whose read state the action changed
+    pub articles: u32,
+}
+
+/// A read article from `Database::read_history`
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub article: Article,
+    pub read_at: Option<DateTime<Utc>>,
+    /// When it was first shown with `tagrss show`
+    pub opened_at: Option<DateTime<Utc>>,
+}
+
+/// Time buckets for `Database::reading_stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Period {
+    Day,
+    #[default]
+    Week,
+    Month,
+}
+
+impl Period {
+    fn strftime(self) -> &'static str {
+        match self {
+            Period::Day => "%Y-%m-%d",
+            Period::Week => "%Y-W%W",
+            Period::Month => "%Y-%m",
+        }
+    }
+}
+
+impl std::str::FromStr for Period {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "day" => Ok(Period::Day),
+            "week" => Ok(Period::Week),
+            "month" => Ok(Period::Month),
+            _ => Err(format!("unknown period '{}' (day, week, month)", s)),
+        }
+    }
+}
+
+/// Articles read in one period under one top-level tag
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadingStat {
+    /// `2026-10-18`, `2026-W41` or `2026-10`
+    pub period: String,
+    pub tag: String,
+    pub read: u32,
+}
+
+/// Rows deleted along with a source by `Database::remove_source`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SourceRemoval {
+    pub articles: u32,
+    pub unread: u32,
+    pub starred: u32,
+    pub manual_tags: u32,
+}
+
+/// An article found by `Database::search`
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub article: Article,
+    /// Matching excerpt with highlighted terms
+    pub snippet: String,
+}
+
+/// The database was written by a newer tagrss with a schema this build does not know
+#[derive(Debug)]
+pub struct SchemaTooNew {
+    pub found: u32,
+    pub supported: u32,
+}
+
+impl fmt::Display for SchemaTooNew {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "database schema version {} is newer than this tagrss supports ({}); upgrade tagrss",
+            self.found, self.supported
+        )
+    }
+}
+
+impl std::error::Error for SchemaTooNew {}
 
 pub struct Database {
     conn: Connection,
 }
 
 impl Database {
+    /// Open a database, creating it or migrating it to the latest schema
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let db = Self::open_unmigrated(path)?;
+        db.migrate()?;
+        Ok(db)
+    }
+
+    /// Open a database without applying pending migrations.
+    /// Fails with `SchemaTooNew` if a newer tagrss has written it.
+    pub fn open_unmigrated<P: AsRef<Path>>(path: P) -> Result<Self> {
         let conn = Connection::open(path)?;
+        conn.pragma_update(None, "foreign_keys", true)?;
         let db = Self { conn };
-        db.init()?;
+        db.register_functions()?;
+        let found = db.schema_version()?;
+        let supported = migrations::latest_version();
+        if found > supported {
+            return Err(SchemaTooNew { found, supported }.into());
+        }
         Ok(db)
     }
 
-    fn init(&self) -> Result<()> {
-        self.conn.execute_batch(
-            r#"
-            CREATE TABLE IF NOT EXISTS sources (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                url TEXT NOT NULL UNIQUE,
-                title TEXT NOT NULL,
-                tags TEXT NOT NULL DEFAULT '[]',
-                last_updated TEXT
-            );
+    /// Schema version stored in the database file
+    pub fn schema_version(&self) -> Result<u32> {
+        let version = self
+            .conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))?;
+        Ok(version)
+    }
 
-            CREATE TABLE IF NOT EXISTS articles (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                source_id INTEGER NOT NULL REFERENCES sources(id),
-                url TEXT NOT NULL UNIQUE,
-                title TEXT NOT NULL,
-                content TEXT,
-                published_at TEXT,
-                word_count INTEGER NOT NULL DEFAULT 0,
-                tags TEXT NOT NULL DEFAULT '[]',
-                read INTEGER NOT NULL DEFAULT 0,
-                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
-            );
+    /// Migrations that `migrate` would apply, oldest first
+    pub fn pending_migrations(&self) -> Result<Vec<&'static Migration>> {
+        let version = self.schema_version()?;
+        Ok(MIGRATIONS.iter().filter(|m| m.version > version).collect())
+    }
 
-            CREATE TABLE IF NOT EXISTS rules (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                rule_json TEXT NOT NULL
-            );
+    /// Bring the schema up to date, one transaction per migration so a failed
+    /// step leaves the database at the previous version.
+    /// Returns the migrations applied.
+    pub fn migrate(&self) -> Result<Vec<&'static Migration>> {
+        let pending = self.pending_migrations()?;
+        if pending.is_empty() {
+            return Ok(pending);
+        }
+        // Foreign keys can only be switched outside a transaction. Migrations
+        // that rebuild a table would otherwise cascade into the tables
+        // referencing it.
+        self.conn.pragma_update(None, "foreign_keys", false)?;
+        let result = self.apply_migrations(&pending);
+        self.conn.pragma_update(None, "foreign_keys", true)?;
+        result?;
+        Ok(pending)
+    }
 
-            CREATE INDEX IF NOT EXISTS idx_articles_source ON articles(source_id);
-            CREATE INDEX IF NOT EXISTS idx_articles_read ON articles(read);
-            "#,
-        )?;
+    fn apply_migrations(&self, pending: &[&'static Migration]) -> Result<()> {
+        for migration in pending {
+            self.transaction(|db| {
+                db.conn
+                    .execute_batch(migration.sql)
+                    .and_then(|_| {
+                        db.conn
+                            .pragma_update(None, "user_version", migration.version)
+                    })
+                    .with_context(|| {
+                        format!(
+                            "Migration {} ({}) failed",
+                            migration.version, migration.description
+                        )
+                    })
+            })?;
+        }
+        Ok(())
+    }
+
+    /// SQL functions used by filters compiled with `Expr::to_sql` and by the
+    /// search index triggers
+    fn register_functions(&self) -> Result<()> {
+        let flags = FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC;
+        self.conn
+            .create_scalar_function("tagrss_glob", 3, flags, |ctx| {
+                let pattern: String = ctx.get(0)?;
+                let tag: String = ctx.get(1)?;
+                let hierarchical: bool = ctx.get(2)?;
+                Ok(tags::glob_match(&pattern, &tag, hierarchical))
+            })?;
+        self.conn
+            .create_scalar_function("tagrss_contains", 2, flags, |ctx| {
+                let haystack: String = ctx.get(0)?;
+                let needle: String = ctx.get(1)?;
+                Ok(folder::contains_ignore_case(&haystack, &needle))
+            })?;
+        self.conn
+            .create_scalar_function("tagrss_strip_html", 1, flags, |ctx| {
+                let html: Option<String> = ctx.get(0)?;
+                Ok(html.map(|h| text::strip_html(&h).trim().to_string()))
+            })?;
+        self.conn
+            .create_scalar_function("tagrss_lang", 2, flags, |ctx| {
+                let title: String = ctx.get(0)?;
+                let content: Option<String> = ctx.get(1)?;
+                Ok(text::detect_lang(&title, content.as_deref()))
+            })?;
         Ok(())
     }
 
+    /// Run `f` in a transaction that is committed if it returns Ok and rolled
+    /// back otherwise. Called inside another `transaction`, `f` joins the
+    /// outer one.
+    pub fn transaction<T>(&self, f: impl FnOnce(&Self) -> Result<T>) -> Result<T> {
+        if !self.conn.is_autocommit() {
+            return f(self);
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        let result = f(self)?;
+        tx.commit()?;
+        Ok(result)
+    }
+
     // === Sources ===
 
     pub fn add_source(&self, url: &str, title: &str, tags: &HashSet<String>) -> Result<i64> {
@@ -68,45 +413,20 @@ impl Database {
     pub fn get_sources(&self) -> Result<Vec<Source>> {
         let mut stmt = self
             .conn
-            .prepare("SELECT id, url, title, tags, last_updated FROM sources")?;
+            .prepare(&format!("SELECT {} FROM sources", SOURCE_COLUMNS))?;
         let sources = stmt
-            .query_map([], |row| {
-                let tags_json: String = row.get(3)?;
-                let last_updated: Option<String> = row.get(4)?;
-                Ok(Source {
-                    id: row.get(0)?,
-                    url: row.get(1)?,
-                    title: row.get(2)?,
-                    tags: serde_json::from_str(&tags_json).unwrap_or_default(),
-                    last_updated: last_updated
-                        .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
-                        .map(|dt| dt.with_timezone(&Utc)),
-                })
-            })?
+            .query_map([], source_from_row)?
             .collect::<Result<Vec<_>, _>>()?;
         Ok(sources)
     }
 
     pub fn get_source(&self, id: i64) -> Result<Option<Source>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT id, url, title, tags, last_updated FROM sources WHERE id = ?1")?;
-        let mut rows = stmt.query(params![id])?;
-        if let Some(row) = rows.next()? {
-            let tags_json: String = row.get(3)?;
-            let last_updated: Option<String> = row.get(4)?;
-            Ok(Some(Source {
-                id: row.get(0)?,
-                url: row.get(1)?,
-                title: row.get(2)?,
-                tags: serde_json::from_str(&tags_json).unwrap_or_default(),
-                last_updated: last_updated
-                    .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
-                    .map(|dt| dt.with_timezone(&Utc)),
-            }))
-        } else {
-            Ok(None)
-        }
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM sources WHERE id = ?1",
+            SOURCE_COLUMNS
+        ))?;
+        let source = stmt.query_row(params![id], source_from_row).optional()?;
+        Ok(source)
     }
 
     pub fn update_source_tags(&self, id: i64, tags: &HashSet<String>) -> Result<()> {
@@ -126,66 +446,342 @@ impl Database {
         Ok(())
     }
 
+    pub fn set_source_enabled(&self, id: i64, enabled: bool) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sources SET enabled = ?1 WHERE id = ?2",
+            params![enabled, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn rename_source(&self, id: i64, title: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sources SET title = ?1 WHERE id = ?2",
+            params![title, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_source_url(&self, id: i64, url: &str) -> Result<()> {
+        let taken = self
+            .conn
+            .query_row(
+                "SELECT id FROM sources WHERE url = ?1 AND id != ?2",
+                params![url, id],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()?;
+        if let Some(other) = taken {
+            anyhow::bail!("Source #{} already uses {}", other, url);
+        }
+        self.conn.execute(
+            "UPDATE sources SET url = ?1 WHERE id = ?2",
+            params![url, id],
+        )?;
+        Ok(())
+    }
+
+    /// What `remove_source` would delete, without changing anything
+    pub fn source_removal(&self, id: i64, keep_articles: bool) -> Result<SourceRemoval> {
+        if keep_articles {
+            return Ok(SourceRemoval::default());
+        }
+        let removal = self.conn.query_row(
+            "SELECT COUNT(*), IFNULL(SUM(read = 0), 0), IFNULL(SUM(starred), 0),
+                    (SELECT COUNT(*) FROM manual_tags m
+                     WHERE m.article_id IN (SELECT id FROM articles WHERE source_id = ?1))
+             FROM articles WHERE source_id = ?1",
+            params![id],
+            |row| {
+                Ok(SourceRemoval {
+                    articles: row.get(0)?,
+                    unread: row.get(1)?,
+                    starred: row.get(2)?,
+                    manual_tags: row.get(3)?,
+                })
+            },
+        )?;
+        Ok(removal)
+    }
+
+    /// Delete a source with its articles and their manual tags, in one transaction.
+    /// With `keep_articles` the articles stay, no longer linked to any source.
+    pub fn remove_source(&self, id: i64, keep_articles: bool) -> Result<SourceRemoval> {
+        self.transaction(|db| {
+            let removal = db.source_removal(id, keep_articles)?;
+            if keep_articles {
+                db.conn.execute(
+                    "UPDATE articles SET source_id = NULL WHERE source_id = ?1",
+                    params![id],
+                )?;
+            } else {
+                db.conn.execute(
+                    "DELETE FROM manual_tags
+                     WHERE article_id IN (SELECT id FROM articles WHERE source_id = ?1)",
+                    params![id],
+                )?;
+                db.conn
+                    .execute("DELETE FROM articles WHERE source_id = ?1", params![id])?;
+            }
+            db.conn
+                .execute("DELETE FROM sources WHERE id = ?1", params![id])?;
+            Ok(removal)
+        })
+    }
+
     // === Articles ===
 
     pub fn add_article(&self, article: &Article) -> Result<i64> {
+        self.insert_article(article)?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Insert many articles in one transaction, skipping URLs already stored.
+    /// Returns how many were added.
+    pub fn add_articles(&self, articles: &[Article]) -> Result<usize> {
+        self.transaction(|db| {
+            let mut added = 0;
+            for article in articles {
+                added += db.insert_article(article)?;
+            }
+            Ok(added)
+        })
+    }
+
+    /// Insert one article unless its URL is stored or was pruned; returns the
+    /// number of rows added
+    fn insert_article(&self, article: &Article) -> Result<usize> {
         let tags_json = serde_json::to_string(&article.tags)?;
         let published = article.published_at.map(|d| d.to_rfc3339());
-        self.conn.execute(
+        let mut stmt = self.conn.prepare_cached(
             r#"INSERT OR IGNORE INTO articles
-               (source_id, url, title, content, published_at, word_count, tags, read)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"#,
-            params![
-                article.source_id,
-                article.url,
-                article.title,
-                article.content,
-                published,
-                article.word_count,
-                tags_json,
-                article.read as i32,
-            ],
+               (source_id, url, title, content, published_at, word_count, tags, read, author,
+                starred, starred_at)
+               SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, CASE WHEN ?10 THEN datetime('now') END
+               WHERE NOT EXISTS (SELECT 1 FROM pruned_urls WHERE url = ?2)"#,
         )?;
-        Ok(self.conn.last_insert_rowid())
+        let added = stmt.execute(params![
+            article.source_id,
+            article.url,
+            article.title,
+            article.content,
+            published,
+            article.word_count,
+            tags_json,
+            article.read as i32,
+            article.author,
+            article.starred,
+        ])?;
+        Ok(added)
     }
 
+    /// Whether an article with this URL is stored or was pruned
     pub fn article_exists(&self, url: &str) -> Result<bool> {
-        let count: i64 = self.conn.query_row(
-            "SELECT COUNT(*) FROM articles WHERE url = ?1",
-            params![url],
-            |row| row.get(0),
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT EXISTS (SELECT 1 FROM articles WHERE url = ?1)
+                 OR EXISTS (SELECT 1 FROM pruned_urls WHERE url = ?1)",
         )?;
-        Ok(count > 0)
+        let exists = stmt.query_row(params![url], |row| row.get(0))?;
+        Ok(exists)
     }
 
     pub fn get_articles(&self) -> Result<Vec<Article>> {
-        let mut stmt = self.conn.prepare(
-            r#"SELECT id, source_id, url, title, content, published_at, word_count, tags, read
-               FROM articles ORDER BY published_at DESC"#,
-        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM articles ORDER BY published_at DESC",
+            ARTICLE_COLUMNS
+        ))?;
         let articles = stmt
-            .query_map([], |row| {
-                let tags_json: String = row.get(7)?;
-                let published: Option<String> = row.get(5)?;
-                let read_int: i32 = row.get(8)?;
-                Ok(Article {
-                    id: row.get(0)?,
-                    source_id: row.get(1)?,
-                    url: row.get(2)?,
-                    title: row.get(3)?,
-                    content: row.get(4)?,
-                    published_at: published
-                        .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
-                        .map(|dt| dt.with_timezone(&Utc)),
-                    word_count: row.get(6)?,
-                    tags: serde_json::from_str(&tags_json).unwrap_or_default(),
-                    read: read_int != 0,
-                })
-            })?
+            .query_map([], article_from_row)?
             .collect::<Result<Vec<_>, _>>()?;
         Ok(articles)
     }
 
+    /// Articles matching a filter in the query's order, with filtering, sorting and the limit
+    /// applied by SQLite instead of loading every article
+    pub fn query_articles(&self, query: &ArticleQuery) -> Result<Vec<Article>> {
+        let (condition, mut params) = query.condition();
+        let (order, order_params) = query.order();
+        params.extend(order_params);
+        let mut sql = format!(
+            "SELECT {} FROM articles WHERE {} ORDER BY {}",
+            ARTICLE_COLUMNS, condition, order
+        );
+        if let Some(limit) = query.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let articles = stmt
+            .query_map(params_from_iter(params), article_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(articles)
+    }
+
+    /// Full-text search over article title, content and author.
+    ///
+    /// `text` uses FTS5 query syntax: words, "exact phrases", prefix*, AND, OR,
+    /// NOT and parentheses. Hits are ranked by BM25 with title matches weighted
+    /// highest, and restricted by the query's filter, mute and unread flag.
+    /// Matched words in the snippet are wrapped in `highlight`.
+    pub fn search(
+        &self,
+        text: &str,
+        query: &ArticleQuery,
+        highlight: (&str, &str),
+    ) -> Result<Vec<SearchHit>> {
+        let (condition, params) = query.condition();
+        let mut sql = format!(
+            "SELECT {}, hits.snippet FROM articles JOIN (
+                 SELECT rowid AS article_id,
+                        snippet(articles_fts, -1, ?1, ?2, '…', 12) AS snippet,
+                        bm25(articles_fts, 10.0, 1.0, 5.0) AS score
+                 FROM articles_fts WHERE articles_fts MATCH ?3
+             ) hits ON hits.article_id = articles.id
+             WHERE {} ORDER BY hits.score, published_at DESC",
+            ARTICLE_COLUMNS, condition
+        );
+        if let Some(limit) = query.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        let mut all: Vec<Value> = vec![
+            Value::Text(highlight.0.to_string()),
+            Value::Text(highlight.1.to_string()),
+            Value::Text(text.to_string()),
+        ];
+        all.extend(params);
+        let mut stmt = self.conn.prepare(&sql)?;
+        let hits = stmt
+            .query_map(params_from_iter(all), |row| {
+                Ok(SearchHit {
+                    article: article_from_row(row)?,
+                    snippet: row.get(13)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Invalid search query: {}", text))?;
+        Ok(hits)
+    }
+
+    /// Total, unread and newest articles matching a query (its limit is ignored)
+    pub fn count_articles(&self, query: &ArticleQuery) -> Result<ArticleCounts> {
+        let (condition, params) = query.condition();
+        let sql = format!(
+            "SELECT COUNT(*), IFNULL(SUM(read = 0), 0), MAX(published_at) FROM articles WHERE {}",
+            condition
+        );
+        let counts = self.conn.query_row(&sql, params_from_iter(params), |row| {
+            Ok(ArticleCounts {
+                total: row.get(0)?,
+                unread: row.get(1)?,
+                newest: parse_date(row.get(2)?),
+            })
+        })?;
+        Ok(counts)
+    }
+
+    /// Counts for several filters at once, in a single pass over the articles
+    /// that are not muted
+    pub fn count_filters(
+        &self,
+        filters: &[Expr],
+        mute: Option<&Expr>,
+    ) -> Result<Vec<ArticleCounts>> {
+        // Three result columns per filter; stay well below SQLite's column limit
+        const CHUNK: usize = 200;
+        let mut counts = Vec::with_capacity(filters.len());
+        for chunk in filters.chunks(CHUNK) {
+            let mut matches = Vec::new();
+            let mut columns = Vec::new();
+            let mut params = Vec::new();
+            for (i, filter) in chunk.iter().enumerate() {
+                let compiled = filter.to_sql();
+                matches.push(format!("({}) AS m{}", compiled.clause, i));
+                params.extend(compiled.params);
+                columns.push(format!(
+                    "IFNULL(SUM(m{i}), 0), IFNULL(SUM(m{i} AND read = 0), 0), \
+                     MAX(CASE WHEN m{i} THEN published_at END)"
+                ));
+            }
+            let mut sql = format!(
+                "SELECT {} FROM (SELECT read, published_at, {} FROM articles",
+                columns.join(", "),
+                matches.join(", ")
+            );
+            if let Some(mute) = mute {
+                let compiled = mute.to_sql();
+                sql.push_str(&format!(" WHERE NOT ({})", compiled.clause));
+                params.extend(compiled.params);
+            }
+            sql.push(')');
+            let row = self.conn.query_row(&sql, params_from_iter(params), |row| {
+                (0..chunk.len())
+                    .map(|i| {
+                        Ok(ArticleCounts {
+                            total: row.get(3 * i)?,
+                            unread: row.get(3 * i + 1)?,
+                            newest: parse_date(row.get(3 * i + 2)?),
+                        })
+                    })
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            })?;
+            counts.extend(row);
+        }
+        Ok(counts)
+    }
+
+    /// Every folder with the counts of its resolved filter, as a tree in file order
+    pub fn folder_counts(
+        &self,
+        folders: &[Folder],
+        mute: Option<&Expr>,
+    ) -> Result<Vec<FolderCount>> {
+        fn paths(nodes: &[FolderNode], out: &mut Vec<String>) {
+            for node in nodes {
+                out.push(node.path.clone());
+                paths(&node.children, out);
+            }
+        }
+        fn attach(
+            nodes: Vec<FolderNode>,
+            counts: &mut impl Iterator<Item = ArticleCounts>,
+        ) -> Vec<FolderCount> {
+            nodes
+                .into_iter()
+                .map(|node| FolderCount {
+                    counts: counts.next().unwrap_or_default(),
+                    filter: node.filter.map(|f| f.to_string()),
+                    children: attach(node.children, counts),
+                    name: node.name,
+                    path: node.path,
+                })
+                .collect()
+        }
+
+        let tree = folder::build_tree(folders);
+        let mut all = Vec::new();
+        paths(&tree, &mut all);
+        let filters: Vec<Expr> = all
+            .iter()
+            .map(|path| folder::resolve(folders, path).map_or(Expr::ALWAYS, |f| f.filter))
+            .collect();
+        let counts = self.count_filters(&filters, mute)?;
+        Ok(attach(tree, &mut counts.into_iter()))
+    }
+
+    pub fn get_article(&self, id: i64) -> Result<Option<Article>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM articles WHERE id = ?1",
+            ARTICLE_COLUMNS
+        ))?;
+        let mut rows = stmt.query(params![id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(article_from_row(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn update_article_tags(&self, id: i64, tags: &HashSet<String>) -> Result<()> {
         let tags_json = serde_json::to_string(tags)?;
         self.conn.execute(
@@ -195,11 +791,441 @@ impl Database {
         Ok(())
     }
 
+    /// Articles the retention policies would delete, oldest first
+    pub fn prune_candidates(&self, retention: &Retention) -> Result<Vec<PruneCandidate>> {
+        if retention.policies.is_empty() {
+            return Ok(Vec::new());
+        }
+        // Starred and queued articles are never deleted
+        let mut kept = (
+            "articles.starred = 0 AND articles.id NOT IN (SELECT article_id FROM read_later)"
+                .to_string(),
+            Vec::new(),
+        );
+        if let Some(keep) = &retention.keep {
+            let compiled = keep.to_sql();
+            kept.0.push_str(&format!(" AND NOT ({})", compiled.clause));
+            kept.1 = compiled.params;
+        }
+
+        let mut cases = String::new();
+        let mut params = Vec::new();
+        for (i, policy) in retention.policies.iter().enumerate() {
+            let (condition, policy_params) = policy_condition(policy, &kept);
+            cases.push_str(&format!(" WHEN {} THEN {}", condition, i));
+            params.extend(policy_params);
+        }
+        let sql = format!(
+            "WITH doomed AS (
+                 SELECT id AS article_id, CASE{} END AS policy FROM articles
+             )
+             SELECT {}, doomed.policy FROM articles
+             JOIN doomed ON doomed.article_id = articles.id
+             WHERE doomed.policy IS NOT NULL
+             ORDER BY {}, articles.id",
+            cases, ARTICLE_COLUMNS, ARTICLE_AGE_BASE
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let candidates = stmt
+            .query_map(params_from_iter(params), |row| {
+                let policy: usize = row.get(13)?;
+                Ok(PruneCandidate {
+                    article: article_from_row(row)?,
+                    policy: retention.policies[policy].name.clone(),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(candidates)
+    }
+
+    /// Delete articles and their manual tags in one transaction, remembering
+    /// their URLs so sync does not fetch them again. Returns how many were deleted.
+    pub fn prune_articles(&self, ids: &[i64]) -> Result<usize> {
+        self.transaction(|db| {
+            let mut remember = db.conn.prepare_cached(
+                "INSERT OR IGNORE INTO pruned_urls (url) SELECT url FROM articles WHERE id = ?1",
+            )?;
+            let mut untag = db
+                .conn
+                .prepare_cached("DELETE FROM manual_tags WHERE article_id = ?1")?;
+            let mut delete = db
+                .conn
+                .prepare_cached("DELETE FROM articles WHERE id = ?1")?;
+            let mut deleted = 0;
+            for id in ids {
+                remember.execute(params![id])?;
+                untag.execute(params![id])?;
+                deleted += delete.execute(params![id])?;
+            }
+            Ok(deleted)
+        })
+    }
+
+    /// Rebuild the database file, returning the space freed by deletions to the OS
+    pub fn vacuum(&self) -> Result<()> {
+        self.conn.execute_batch("VACUUM")?;
+        Ok(())
+    }
+
+    // === Manual tags ===
+
+    /// Add or remove tags on a single article by hand.
+    ///
+    /// The choice is remembered in `manual_tags` so that recomputing tags from
+    /// source and rules (`rule apply`) keeps it. Returns the article's new tags.
+    pub fn set_manual_tags(
+        &self,
+        article_id: i64,
+        tags: &HashSet<String>,
+        removed: bool,
+    ) -> Result<HashSet<String>> {
+        self.transaction(|db| {
+            let tags_json: String = db
+                .conn
+                .query_row(
+                    "SELECT tags FROM articles WHERE id = ?1",
+                    params![article_id],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .ok_or_else(|| anyhow::anyhow!("No article #{}", article_id))?;
+            let mut current: HashSet<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+
+            for tag in tags {
+                db.conn.execute(
+                    "INSERT OR REPLACE INTO manual_tags (article_id, tag, removed)
+                     VALUES (?1, ?2, ?3)",
+                    params![article_id, tag, removed as i32],
+                )?;
+                if removed {
+                    current.remove(tag);
+                } else {
+                    current.insert(tag.clone());
+                }
+            }
+            db.conn.execute(
+                "UPDATE articles SET tags = ?1 WHERE id = ?2",
+                params![serde_json::to_string(&current)?, article_id],
+            )?;
+            Ok(current)
+        })
+    }
+
+    /// Manual tag overrides for every article that has any
+    pub fn get_manual_tags(&self) -> Result<HashMap<i64, ManualTags>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT article_id, tag, removed FROM manual_tags")?;
+        let mut rows = stmt.query([])?;
+        let mut map: HashMap<i64, ManualTags> = HashMap::new();
+        while let Some(row) = rows.next()? {
+            let article_id: i64 = row.get(0)?;
+            let tag: String = row.get(1)?;
+            let removed: i32 = row.get(2)?;
+            let entry = map.entry(article_id).or_default();
+            if removed != 0 {
+                entry.removed.insert(tag);
+            } else {
+                entry.added.insert(tag);
+            }
+        }
+        Ok(map)
+    }
+
+    // === Reading history ===
+
+    /// Mark one article read or unread, as an action `undo_read` can revert
     pub fn mark_read(&self, id: i64, read: bool) -> Result<()> {
-        self.conn.execute(
-            "UPDATE articles SET read = ?1 WHERE id = ?2",
-            params![read as i32, id],
+        let description = format!("mark #{} {}", id, if read { "read" } else { "unread" });
+        self.set_read(&[id], read, &description)?;
+        Ok(())
+    }
+
+    /// Mark articles read or unread as one action that `undo_read` can revert,
+    /// recording when each was read. Articles already in that state are left
+    /// alone and not recorded. Articles marked read also leave the read-later
+    /// queue (undo does not put them back). Returns how many changed.
+    pub fn set_read(&self, ids: &[i64], read: bool, description: &str) -> Result<usize> {
+        let ids = serde_json::to_string(ids)?;
+        self.set_read_where(
+            "articles.id IN (SELECT value FROM json_each(?))",
+            vec![Value::Text(ids)],
+            read,
+            description,
+        )
+    }
+
+    /// Like `set_read`, for every article a query selects (its limit and sort
+    /// are ignored). One UPDATE statement, however many articles match.
+    pub fn set_read_matching(
+        &self,
+        query: &ArticleQuery,
+        read: bool,
+        description: &str,
+    ) -> Result<usize> {
+        let (condition, params) = query.condition();
+        self.set_read_where(&condition, params, read, description)
+    }
+
+    /// Record the articles matching `condition` whose read state will change,
+    /// then change them all
+    fn set_read_where(
+        &self,
+        condition: &str,
+        condition_params: Vec<Value>,
+        read: bool,
+        description: &str,
+    ) -> Result<usize> {
+        self.transaction(|db| {
+            let now = now_timestamp();
+            db.conn.execute(
+                "INSERT INTO read_actions (description, created_at) VALUES (?1, ?2)",
+                params![description, now],
+            )?;
+            let action = db.conn.last_insert_rowid();
+
+            let mut params = vec![Value::Integer(action)];
+            params.extend(condition_params.iter().cloned());
+            params.push(Value::Integer(read as i64));
+            let changed = db.conn.execute(
+                &format!(
+                    "INSERT INTO read_action_articles (action_id, article_id, was_read, was_read_at)
+                     SELECT ?, id, read, read_at FROM articles WHERE ({}) AND read != ?",
+                    condition
+                ),
+                params_from_iter(params),
+            )?;
+            db.conn.execute(
+                "UPDATE articles SET read = ?1, read_at = CASE WHEN ?1 THEN ?2 END
+                 WHERE id IN (SELECT article_id FROM read_action_articles WHERE action_id = ?3)",
+                params![read, now, action],
+            )?;
+            if read {
+                let dequeued = db.conn.execute(
+                    &format!(
+                        "DELETE FROM read_later WHERE article_id IN (SELECT id FROM articles WHERE {})",
+                        condition
+                    ),
+                    params_from_iter(condition_params),
+                )?;
+                if dequeued > 0 {
+                    db.write_queue(&db.queue_ids()?)?;
+                }
+            }
+
+            if changed == 0 {
+                db.conn
+                    .execute("DELETE FROM read_actions WHERE id = ?1", params![action])?;
+            }
+            db.conn.execute(
+                "DELETE FROM read_actions WHERE id <= ?1",
+                params![action - UNDO_DEPTH],
+            )?;
+            Ok(changed)
+        })
+    }
+
+    /// The most recent mark-read action that can be undone
+    pub fn last_read_action(&self) -> Result<Option<ReadAction>> {
+        let action = self
+            .conn
+            .query_row(
+                "SELECT id, description, created_at,
+                        (SELECT COUNT(*) FROM read_action_articles r WHERE r.action_id = a.id)
+                 FROM read_actions a ORDER BY id DESC LIMIT 1",
+                [],
+                |row| {
+                    Ok(ReadAction {
+                        id: row.get(0)?,
+                        description: row.get(1)?,
+                        at: parse_date(row.get(2)?),
+                        articles: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(action)
+    }
+
+    /// Revert the most recent mark-read action, restoring each article's read
+    /// state and time. Returns the action, or None if there is nothing to undo.
+    /// Articles deleted since are skipped.
+    pub fn undo_read(&self) -> Result<Option<ReadAction>> {
+        self.transaction(|db| {
+            let Some(action) = db.last_read_action()? else {
+                return Ok(None);
+            };
+            db.conn.execute(
+                "UPDATE articles SET read = r.was_read, read_at = r.was_read_at
+                 FROM read_action_articles r
+                 WHERE r.action_id = ?1 AND r.article_id = articles.id",
+                params![action.id],
+            )?;
+            db.conn
+                .execute("DELETE FROM read_actions WHERE id = ?1", params![action.id])?;
+            Ok(Some(action))
+        })
+    }
+
+    /// Record that an article was opened, keeping the first time.
+    /// Returns false if there is no such article.
+    pub fn mark_opened(&self, id: i64) -> Result<bool> {
+        let changed = self.conn.execute(
+            "UPDATE articles SET opened_at = IFNULL(opened_at, ?1) WHERE id = ?2",
+            params![now_timestamp(), id],
         )?;
+        Ok(changed > 0)
+    }
+
+    /// Read articles, most recently read first. Articles read before read
+    /// times were recorded are left out.
+    pub fn read_history(
+        &self,
+        since: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<Vec<HistoryEntry>> {
+        let mut sql = format!(
+            "SELECT {}, read_at, opened_at FROM articles
+             WHERE read = 1 AND read_at >= ?1
+             ORDER BY read_at DESC, id DESC",
+            ARTICLE_COLUMNS
+        );
+        if let Some(limit) = limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+        // The empty string sorts before every timestamp
+        let since = since.map_or(String::new(), |d| {
+            d.to_rfc3339_opts(SecondsFormat::Millis, true)
+        });
+        let mut stmt = self.conn.prepare(&sql)?;
+        let entries = stmt
+            .query_map(params![since], |row| {
+                Ok(HistoryEntry {
+                    article: article_from_row(row)?,
+                    read_at: parse_date(row.get(13)?),
+                    opened_at: parse_date(row.get(14)?),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    /// Articles read per period and top-level tag (`tech` for `tech/ai`),
+    /// newest period first and most read tag first within it
+    pub fn reading_stats(
+        &self,
+        period: Period,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<ReadingStat>> {
+        // The empty string sorts before every timestamp
+        let since = since.map_or(String::new(), |d| {
+            d.to_rfc3339_opts(SecondsFormat::Millis, true)
+        });
+        let mut stmt = self.conn.prepare(
+            "SELECT strftime(?1, a.read_at) AS period,
+                    CASE WHEN instr(t.tag, '/') > 0
+                         THEN substr(t.tag, 1, instr(t.tag, '/') - 1)
+                         ELSE t.tag END AS top,
+                    COUNT(DISTINCT a.id) AS n
+             FROM articles a JOIN article_tags t ON t.article_id = a.id
+             WHERE a.read = 1 AND a.read_at >= ?2
+             GROUP BY period, top
+             ORDER BY period DESC, n DESC, top",
+        )?;
+        let stats = stmt
+            .query_map(params![period.strftime(), since], |row| {
+                Ok(ReadingStat {
+                    period: row.get(0)?,
+                    tag: row.get(1)?,
+                    read: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(stats)
+    }
+
+    // === Stars and the read-later queue ===
+
+    /// Star or unstar an article. Returns false if there is no such article.
+    pub fn set_starred(&self, id: i64, starred: bool) -> Result<bool> {
+        let changed = self.conn.execute(
+            "UPDATE articles
+             SET starred = ?1,
+                 starred_at = CASE WHEN ?1 THEN IFNULL(starred_at, datetime('now')) END
+             WHERE id = ?2",
+            params![starred, id],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// Starred articles, most recently starred first
+    pub fn starred_articles(&self) -> Result<Vec<Article>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM articles WHERE starred = 1 ORDER BY starred_at DESC, id DESC",
+            ARTICLE_COLUMNS
+        ))?;
+        let articles = stmt
+            .query_map([], article_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(articles)
+    }
+
+    /// Articles queued to read later, in reading order
+    pub fn read_later(&self) -> Result<Vec<Article>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM articles JOIN read_later q ON q.article_id = articles.id
+             ORDER BY q.position",
+            ARTICLE_COLUMNS
+        ))?;
+        let articles = stmt
+            .query_map([], article_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(articles)
+    }
+
+    /// Put an article at the end of the read-later queue, or move it to
+    /// `position` (1-based, clamped to the queue). Returns its position.
+    pub fn enqueue(&self, id: i64, position: Option<usize>) -> Result<usize> {
+        self.transaction(|db| {
+            let mut queue = db.queue_ids()?;
+            queue.retain(|&queued| queued != id);
+            let index = position.map_or(queue.len(), |p| p.clamp(1, queue.len() + 1) - 1);
+            queue.insert(index, id);
+            db.write_queue(&queue)?;
+            Ok(index + 1)
+        })
+    }
+
+    /// Take an article off the read-later queue. Returns false if it was not queued.
+    pub fn dequeue(&self, id: i64) -> Result<bool> {
+        self.transaction(|db| {
+            let mut queue = db.queue_ids()?;
+            let before = queue.len();
+            queue.retain(|&queued| queued != id);
+            db.write_queue(&queue)?;
+            Ok(queue.len() < before)
+        })
+    }
+
+    fn queue_ids(&self) -> Result<Vec<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT article_id FROM read_later ORDER BY position")?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ids)
+    }
+
+    /// Replace the queue, numbering positions from 1
+    fn write_queue(&self, ids: &[i64]) -> Result<()> {
+        self.conn.execute("DELETE FROM read_later", [])?;
+        let mut stmt = self
+            .conn
+            .prepare_cached("INSERT INTO read_later (article_id, position) VALUES (?1, ?2)")?;
+        for (i, id) in ids.iter().enumerate() {
+            stmt.execute(params![id, i as i64 + 1])?;
+        }
         Ok(())
     }
 
@@ -230,4 +1256,202 @@ impl Database {
             .execute("DELETE FROM rules WHERE id = ?1", params![id])?;
         Ok(())
     }
+
+    // === Classifier ===
+
+    /// Store the trained classifier, replacing any previous one
+    pub fn save_classifier(&self, model: &NaiveBayes) -> Result<()> {
+        let json = serde_json::to_string(model)?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO classifier (id, model_json, trained_at) VALUES (1, ?1, ?2)",
+            params![json, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn load_classifier(&self) -> Result<Option<NaiveBayes>> {
+        let json: Option<String> = self
+            .conn
+            .query_row("SELECT model_json FROM classifier WHERE id = 1", [], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        Ok(json.map(|j| serde_json::from_str(&j)).transpose()?)
+    }
+
+    // === Tags ===
+
+    /// All tags assigned to any source or article
+    pub fn all_tags(&self) -> Result<HashSet<String>> {
+        let mut all = HashSet::new();
+        let mut stmt = self
+            .conn
+            .prepare("SELECT tag FROM source_tags UNION SELECT tag FROM article_tags")?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            all.insert(row.get(0)?);
+        }
+        Ok(all)
+    }
+
+    /// Articles carrying `tag` or one of its descendants, newest first.
+    /// Looked up through the tag index instead of scanning every article.
+    pub fn articles_with_tag(&self, tag: &str) -> Result<Vec<Article>> {
+        let (low, high) = tags::child_range(tag);
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM articles WHERE id IN (
+                 SELECT article_id FROM article_tags
+                 WHERE tag = ?1 OR (tag >= ?2 AND tag < ?3)
+             ) ORDER BY published_at DESC",
+            ARTICLE_COLUMNS
+        ))?;
+        let articles = stmt
+            .query_map(params![tag, low, high], article_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(articles)
+    }
+
+    /// Sources carrying `tag` or one of its descendants
+    pub fn sources_with_tag(&self, tag: &str) -> Result<Vec<Source>> {
+        let (low, high) = tags::child_range(tag);
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM sources WHERE id IN (
+                 SELECT source_id FROM source_tags
+                 WHERE tag = ?1 OR (tag >= ?2 AND tag < ?3)
+             ) ORDER BY id",
+            SOURCE_COLUMNS
+        ))?;
+        let sources = stmt
+            .query_map(params![tag, low, high], source_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(sources)
+    }
+
+    /// Article counts per tag, including every ancestor level.
+    ///
+    /// An article tagged "tech/ai/llm" counts once towards "tech", "tech/ai"
+    /// and "tech/ai/llm", even if it also carries "tech/ai" directly.
+    /// Only the articles selected by `query`'s filter, mute, source and unread
+    /// flag are counted; its limit and sort are ignored.
+    pub fn tag_counts(&self, query: &ArticleQuery) -> Result<Vec<TagCount>> {
+        let (condition, params) = query.condition();
+        // rtrim(tag, replace(tag, '/', '')) strips the last segment, leaving the parent + '/'
+        let mut stmt = self.conn.prepare(&format!(
+            r#"WITH RECURSIVE t(id, read, tag) AS (
+                   SELECT a.id, a.read, t.tag
+                   FROM (SELECT id, read FROM articles WHERE {}) a
+                   JOIN article_tags t ON t.article_id = a.id
+                   UNION
+                   SELECT id, read, rtrim(rtrim(tag, replace(tag, '/', '')), '/')
+                   FROM t WHERE instr(tag, '/') > 0
+               )
+               SELECT tag, COUNT(*), SUM(read = 0) FROM t GROUP BY tag ORDER BY tag"#,
+            condition
+        ))?;
+        let counts = stmt
+            .query_map(params_from_iter(params), |row| {
+                Ok(TagCount {
+                    tag: row.get(0)?,
+                    total: row.get(1)?,
+                    unread: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(counts)
+    }
+
+    /// Apply tag edits to sources, articles and rules in a single transaction
+    pub fn rewrite_tags(&self, edits: &[TagEdit]) -> Result<TagEditReport> {
+        self.transaction(|db| {
+            let mut report = TagEditReport::default();
+
+            for table in ["sources", "articles"] {
+                let rows: Vec<(i64, String)> = {
+                    let mut stmt = db
+                        .conn
+                        .prepare(&format!("SELECT id, tags FROM {}", table))?;
+                    let rows = stmt
+                        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                        .collect::<Result<Vec<_>, _>>()?;
+                    rows
+                };
+                let mut update = db
+                    .conn
+                    .prepare(&format!("UPDATE {} SET tags = ?1 WHERE id = ?2", table))?;
+                for (id, tags_json) in rows {
+                    let old: HashSet<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+                    let new = tags::apply_all(edits, &old);
+                    if new != old {
+                        update.execute(params![serde_json::to_string(&new)?, id])?;
+                        if table == "sources" {
+                            report.sources += 1;
+                        } else {
+                            report.articles += 1;
+                        }
+                    }
+                }
+            }
+
+            // Manual overrides follow renames and disappear with deleted tags
+            let manual: Vec<(i64, String, i32)> = {
+                let mut stmt = db
+                    .conn
+                    .prepare("SELECT article_id, tag, removed FROM manual_tags")?;
+                let rows = stmt
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                    .collect::<Result<Vec<_>, _>>()?;
+                rows
+            };
+            for (article_id, tag, removed) in manual {
+                let new_tag = edits
+                    .iter()
+                    .try_fold(tag.clone(), |t, edit| edit.rewrite(&t));
+                if new_tag.as_ref() == Some(&tag) {
+                    continue;
+                }
+                db.conn.execute(
+                    "DELETE FROM manual_tags WHERE article_id = ?1 AND tag = ?2",
+                    params![article_id, tag],
+                )?;
+                if let Some(new_tag) = new_tag {
+                    db.conn.execute(
+                        "INSERT OR REPLACE INTO manual_tags (article_id, tag, removed)
+                         VALUES (?1, ?2, ?3)",
+                        params![article_id, new_tag, removed],
+                    )?;
+                }
+            }
+
+            // Rules that add a renamed tag follow the rename; rules for deleted tags
+            // are kept and reported
+            let rules: Vec<(i64, String)> = {
+                let mut stmt = db.conn.prepare("SELECT id, rule_json FROM rules")?;
+                let rows = stmt
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .collect::<Result<Vec<_>, _>>()?;
+                rows
+            };
+            for (id, json) in rules {
+                let mut rule: Rule = serde_json::from_str(&json)?;
+                let old_tag = rule.tag().to_string();
+                let new_tag = edits
+                    .iter()
+                    .try_fold(old_tag.clone(), |tag, edit| edit.rewrite(&tag));
+                let Some(new_tag) = new_tag else {
+                    report.kept_rules.push(id);
+                    continue;
+                };
+                if new_tag != old_tag {
+                    rule.set_tag(new_tag);
+                    db.conn.execute(
+                        "UPDATE rules SET rule_json = ?1 WHERE id = ?2",
+                        params![serde_json::to_string(&rule)?, id],
+                    )?;
+                    report.rules += 1;
+                }
+            }
+
+            Ok(report)
+        })
+    }
 }
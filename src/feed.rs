@@ -2,12 +2,14 @@ use anyhow::Result;
 use chrono::{DateTime, Utc};
 use std::collections::HashSet;
 
+use crate::classify::NaiveBayes;
 use crate::db::Database;
+use crate::folder::Mute;
 use crate::models::{Article, Rule, Source};
 
 /// Fetch and parse a feed from URL
-pub async fn fetch_feed(url: &str) -> Result<(String, Vec<RawEntry>)> {
-    let response = reqwest::get(url).await?.bytes().await?;
+pub async fn fetch_feed(client: &reqwest::Client, url: &str) -> Result<(String, Vec<RawEntry>)> {
+    let response = client.get(url).send().await?.bytes().await?;
     let feed = feed_rs::parser::parse(&response[..])?;
 
     let title = feed
@@ -42,6 +44,7 @@ pub async fn fetch_feed(url: &str) -> Result<(String, Vec<RawEntry>)> {
                     .map(|t| t.content)
                     .unwrap_or_else(|| "Untitled".to_string()),
                 content,
+                author: e.authors.first().map(|p| p.name.clone()),
                 published_at: published,
                 word_count,
             }
@@ -57,6 +60,7 @@ pub struct RawEntry {
     pub url: String,
     pub title: String,
     pub content: Option<String>,
+    pub author: Option<String>,
     pub published_at: Option<DateTime<Utc>>,
     pub word_count: u32,
 }
@@ -71,44 +75,64 @@ fn count_words(html: &str) -> u32 {
     text.split_whitespace().count() as u32
 }
 
-/// Sync a source: fetch feed and add new articles
-pub async fn sync_source(db: &Database, source: &Source, rules: &[(i64, Rule)]) -> Result<u32> {
-    let (_, entries) = fetch_feed(&source.url).await?;
-    let mut added = 0;
-
-    for entry in entries {
-        if db.article_exists(&entry.url)? {
-            continue;
-        }
+/// Sync a source: fetch feed and add new articles.
+/// Articles caught by a `mark_read` mute are stored already read.
+///
+/// All database writes for the source happen in one transaction, so a failed
+/// sync leaves neither partial articles nor an updated timestamp behind.
+pub async fn sync_source(
+    db: &Database,
+    client: &reqwest::Client,
+    source: &Source,
+    rules: &[(i64, Rule)],
+    model: Option<&NaiveBayes>,
+    mutes: &[Mute],
+) -> Result<u32> {
+    let (_, entries) = fetch_feed(client, &source.url).await?;
+
+    db.transaction(|db| {
+        let mut articles = Vec::new();
+        for entry in entries {
+            if db.article_exists(&entry.url)? {
+                continue;
+            }
 
-        // Start with tags inherited from source
-        let mut tags = source.tags.clone();
-
-        // Create article (temporarily without final tags for rule evaluation)
-        let mut article = Article {
-            id: 0,
-            source_id: source.id,
-            url: entry.url,
-            title: entry.title,
-            content: entry.content,
-            published_at: entry.published_at,
-            word_count: entry.word_count,
-            tags: HashSet::new(),
-            read: false,
-        };
-
-        // Apply rules
-        for (_, rule) in rules {
-            if let Some(tag) = rule.apply(&article) {
-                tags.insert(tag);
+            // Start with tags inherited from source
+            let mut tags = source.tags.clone();
+
+            // Create article (temporarily without final tags for rule evaluation)
+            let mut article = Article {
+                id: 0,
+                source_id: source.id,
+                source_title: source.title.clone(),
+                url: entry.url,
+                title: entry.title,
+                content: entry.content,
+                author: entry.author,
+                published_at: entry.published_at,
+                word_count: entry.word_count,
+                tags: HashSet::new(),
+                manual_tags: HashSet::new(),
+                read: false,
+                starred: false,
+            };
+
+            // Apply rules
+            for (_, rule) in rules {
+                if let Some(tag) = rule.apply_with(&article, model) {
+                    tags.insert(tag);
+                }
             }
-        }
 
-        article.tags = tags;
-        db.add_article(&article)?;
-        added += 1;
-    }
+            article.tags = tags;
+            article.read = mutes
+                .iter()
+                .any(|m| m.mark_read && m.filter.matches(&article));
+            articles.push(article);
+        }
 
-    db.update_source_timestamp(source.id, Utc::now())?;
-    Ok(added)
+        let added = db.add_articles(&articles)?;
+        db.update_source_timestamp(source.id, Utc::now())?;
+        Ok(added as u32)
+    })
 }
@@ -1,19 +1,381 @@
-use crate::models::Article;
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::types::Value;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::models::Article;
+use crate::{tags, text};
 
-/// A folder is a named filter expression
+/// A folder is a named filter expression.
+///
+/// Folders nest by path like tags: "Tech/AI" is a child of "Tech", and only
+/// shows articles that also match the parent's filter.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Folder {
     pub name: String,
     pub filter: Expr,
+    #[serde(default)]
+    pub view: FolderView,
 }
 
-/// Boolean expression over tags
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// How a folder lists its articles unless overridden on the command line
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FolderView {
+    #[serde(default)]
+    pub sort: SortOrder,
+    /// Only show unread articles
+    #[serde(default)]
+    pub unread: bool,
+    /// Number of articles shown
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub group: Grouping,
+}
+
+/// Order of listed articles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// Most recently published first
+    #[default]
+    Newest,
+    Oldest,
+    /// Fewest words first
+    Shortest,
+    /// Articles carrying the most of the filter's tags first, then newest
+    Score,
+    /// By source title, then newest
+    Source,
+}
+
+/// How listed articles are grouped under headings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Grouping {
+    #[default]
+    None,
+    Source,
+    /// By publication date (UTC)
+    Day,
+    /// Under each of the article's tags
+    Tag,
+}
+
+impl std::str::FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "newest" => Ok(SortOrder::Newest),
+            "oldest" => Ok(SortOrder::Oldest),
+            "shortest" => Ok(SortOrder::Shortest),
+            "score" => Ok(SortOrder::Score),
+            "source" => Ok(SortOrder::Source),
+            _ => Err(format!(
+                "unknown sort '{}', expected newest, oldest, shortest, score or source",
+                s
+            )),
+        }
+    }
+}
+
+impl std::str::FromStr for Grouping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Grouping::None),
+            "source" => Ok(Grouping::Source),
+            "day" => Ok(Grouping::Day),
+            "tag" => Ok(Grouping::Tag),
+            _ => Err(format!(
+                "unknown grouping '{}', expected none, source, day or tag",
+                s
+            )),
+        }
+    }
+}
+
+/// Split sorted articles into headed groups. Groups appear in the order of
+/// their first article and keep the articles' order; with `Grouping::Tag`
+/// an article is listed under each of its tags.
+pub fn group_articles(articles: &[Article], grouping: Grouping) -> Vec<(String, Vec<&Article>)> {
+    let mut groups: Vec<(String, Vec<&Article>)> = Vec::new();
+    for article in articles {
+        let keys = match grouping {
+            Grouping::None => vec![String::new()],
+            Grouping::Source => vec![article.source_title.clone()],
+            Grouping::Day => vec![article
+                .published_at
+                .map_or("undated".to_string(), |p| p.date_naive().to_string())],
+            Grouping::Tag => {
+                let mut tags: Vec<String> = article.tags.iter().cloned().collect();
+                tags.sort();
+                if tags.is_empty() {
+                    tags.push("untagged".to_string());
+                }
+                tags
+            }
+        };
+        for key in keys {
+            match groups.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, group)) => group.push(article),
+                None => groups.push((key, vec![article])),
+            }
+        }
+    }
+    groups
+}
+
+impl Folder {
+    /// Path of the parent folder: "Tech/AI" -> Some("Tech")
+    pub fn parent(&self) -> Option<&str> {
+        self.name.rsplit_once('/').map(|(parent, _)| parent)
+    }
+}
+
+/// Look up a folder by path, with its filter ANDed with the filters of all
+/// its ancestors. A path that is only implied by nested folders ("Tech" when
+/// just "Tech/AI" and "Tech/Web" are defined) matches what any of them match.
+pub fn resolve(folders: &[Folder], path: &str) -> Option<Folder> {
+    if !folders.iter().any(|f| tags::is_under(&f.name, path)) {
+        return None;
+    }
+    let mut ancestors: Vec<&Folder> = folders
+        .iter()
+        .filter(|f| tags::is_under(path, &f.name))
+        .collect();
+    ancestors.sort_by_key(|f| f.name.len());
+    let mut filters: Vec<Expr> = ancestors.iter().map(|f| f.filter.clone()).collect();
+    if ancestors.last().is_none_or(|f| f.name != path) {
+        filters.push(implied_filter(folders, path));
+    }
+    let filter = match filters.len() {
+        1 => filters.remove(0),
+        _ => Expr::And { exprs: filters },
+    };
+    let view = folders
+        .iter()
+        .find(|f| f.name == path)
+        .map(|f| f.view.clone())
+        .unwrap_or_default();
+    Some(Folder {
+        name: path.to_string(),
+        filter,
+        view,
+    })
+}
+
+/// The filter of a folder path: its own if defined, otherwise the OR of its
+/// children's
+fn implied_filter(folders: &[Folder], path: &str) -> Expr {
+    if let Some(folder) = folders.iter().find(|f| f.name == path) {
+        return folder.filter.clone();
+    }
+    let prefix = format!("{}/", path);
+    let mut children: Vec<&str> = Vec::new();
+    for f in folders {
+        if let Some(rest) = f.name.strip_prefix(&prefix) {
+            let child = &f.name[..prefix.len() + rest.split('/').next().unwrap_or(rest).len()];
+            if !children.contains(&child) {
+                children.push(child);
+            }
+        }
+    }
+    let mut exprs: Vec<Expr> = children
+        .into_iter()
+        .map(|child| implied_filter(folders, child))
+        .collect();
+    match exprs.len() {
+        1 => exprs.remove(0),
+        _ => Expr::Or { exprs },
+    }
+}
+
+/// A node in the folder hierarchy
+#[derive(Debug, Clone, PartialEq)]
+pub struct FolderNode {
+    /// Last path segment, e.g. "AI" for Tech/AI
+    pub name: String,
+    /// Full folder path
+    pub path: String,
+    /// The folder's own filter; None for a level only implied by a child's path
+    pub filter: Option<Expr>,
+    pub children: Vec<FolderNode>,
+}
+
+/// Arrange folders into a tree, keeping their order from the folders file.
+/// Missing intermediate levels are created without a filter.
+pub fn build_tree(folders: &[Folder]) -> Vec<FolderNode> {
+    let mut roots = Vec::new();
+    for folder in folders {
+        let segments: Vec<&str> = folder.name.split('/').collect();
+        insert_node(&mut roots, &segments, 1, folder);
+    }
+    roots
+}
+
+fn insert_node(nodes: &mut Vec<FolderNode>, segments: &[&str], depth: usize, folder: &Folder) {
+    let name = segments[depth - 1];
+    let idx = match nodes.iter().position(|n| n.name == name) {
+        Some(idx) => idx,
+        None => {
+            nodes.push(FolderNode {
+                name: name.to_string(),
+                path: segments[..depth].join("/"),
+                filter: None,
+                children: Vec::new(),
+            });
+            nodes.len() - 1
+        }
+    };
+    let node = &mut nodes[idx];
+    if depth == segments.len() {
+        node.filter = Some(folder.filter.clone());
+    } else {
+        insert_node(&mut node.children, segments, depth + 1, folder);
+    }
+}
+
+/// A mute hides matching articles from every list, count and tag tree
+#[derive(Debug, Clone)]
+pub struct Mute {
+    pub name: String,
+    pub filter: Expr,
+    /// Mark new matching articles read at sync time
+    pub mark_read: bool,
+}
+
+/// One filter matching every muted article; None if nothing is muted
+pub fn mute_filter(mutes: &[Mute]) -> Option<Expr> {
+    match mutes {
+        [] => None,
+        [mute] => Some(mute.filter.clone()),
+        _ => Some(Expr::Or {
+            exprs: mutes.iter().map(|m| m.filter.clone()).collect(),
+        }),
+    }
+}
+
+/// Number of articles matching a filter, and when the newest was published
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ArticleCounts {
+    pub total: u32,
+    pub unread: u32,
+    pub newest: Option<DateTime<Utc>>,
+}
+
+/// A node in the folder hierarchy with the counts of its resolved filter
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FolderCount {
+    /// Last path segment, e.g. "AI" for Tech/AI
+    pub name: String,
+    /// Full folder path
+    pub path: String,
+    /// The folder's own filter; None for a level only implied by a child's path
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    #[serde(flatten)]
+    pub counts: ArticleCounts,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<FolderCount>,
+}
+
+/// Comparison operator in field predicates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Cmp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+}
+
+impl Cmp {
+    pub fn holds<T: PartialOrd>(self, left: T, right: T) -> bool {
+        match self {
+            Cmp::Lt => left < right,
+            Cmp::Le => left <= right,
+            Cmp::Gt => left > right,
+            Cmp::Ge => left >= right,
+            Cmp::Eq => left == right,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Cmp::Lt => "<",
+            Cmp::Le => "<=",
+            Cmp::Gt => ">",
+            Cmp::Ge => ">=",
+            Cmp::Eq => "=",
+        }
+    }
+}
+
+/// Unit of an `age` predicate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AgeUnit {
+    Hours,
+    Days,
+    Weeks,
+}
+
+impl AgeUnit {
+    pub fn seconds(self) -> i64 {
+        match self {
+            AgeUnit::Hours => 3600,
+            AgeUnit::Days => 86400,
+            AgeUnit::Weeks => 7 * 86400,
+        }
+    }
+
+    pub fn suffix(self) -> char {
+        match self {
+            AgeUnit::Hours => 'h',
+            AgeUnit::Days => 'd',
+            AgeUnit::Weeks => 'w',
+        }
+    }
+}
+
+/// Boolean expression over tags and article fields
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "op")]
 pub enum Expr {
     /// Match a tag (including hierarchical children)
     Tag { name: String },
+    /// Match exactly this tag, not its children: `=tech`
+    Exact { name: String },
+    /// Match tags against a glob, hierarchically like `Tag` unless `exact`:
+    /// `*` matches within one path segment, `**` any number of segments.
+    Glob {
+        pattern: String,
+        #[serde(default)]
+        exact: bool,
+    },
+    /// Read state: `read` / `unread`
+    Read { read: bool },
+    /// Starred state: `starred` / `unstarred`
+    Starred { starred: bool },
+    /// Source title contains text, case-insensitive: `source:"Peter Turchin"`
+    Source { name: String },
+    /// Word count comparison: `words>2000`
+    Words { cmp: Cmp, count: u32 },
+    /// Time since publication: `age<3d` (units h, d, w)
+    Age {
+        cmp: Cmp,
+        amount: u32,
+        unit: AgeUnit,
+    },
+    /// Publication date comparison: `published>2026-01-01`
+    Published { cmp: Cmp, date: NaiveDate },
+    /// Title contains text, case-insensitive: `title~"rust"`
+    Title { text: String },
+    /// Title or content contains text, case-insensitive: `text~"sponsored"`
+    Text { text: String },
+    /// Detected content language: `lang:zh`
+    Lang { code: String },
     /// Logical AND
     And { exprs: Vec<Expr> },
     /// Logical OR
@@ -23,135 +385,955 @@ pub enum Expr {
 }
 
 impl Expr {
+    /// Matches every article: an empty And
+    pub const ALWAYS: Expr = Expr::And { exprs: Vec::new() };
+    /// Matches no article: an empty Or
+    pub const NEVER: Expr = Expr::Or { exprs: Vec::new() };
+
     pub fn matches(&self, article: &Article) -> bool {
         match self {
             Expr::Tag { name } => article.match_tag(name),
+            Expr::Exact { name } => article.tags.contains(name),
+            Expr::Glob { pattern, exact } => article
+                .tags
+                .iter()
+                .any(|t| tags::glob_match(pattern, t, !exact)),
+            Expr::Read { read } => article.read == *read,
+            Expr::Starred { starred } => article.starred == *starred,
+            Expr::Source { name } => contains_ignore_case(&article.source_title, name),
+            Expr::Words { cmp, count } => cmp.holds(article.word_count, *count),
+            Expr::Age { cmp, amount, unit } => article.published_at.is_some_and(|p| {
+                let age = (Utc::now() - p).num_seconds();
+                cmp.holds(age, *amount as i64 * unit.seconds())
+            }),
+            Expr::Published { cmp, date } => article
+                .published_at
+                .is_some_and(|p| cmp.holds(p.date_naive(), *date)),
+            Expr::Title { text } => contains_ignore_case(&article.title, text),
+            Expr::Text { text } => {
+                contains_ignore_case(&article.title, text)
+                    || article
+                        .content
+                        .as_deref()
+                        .is_some_and(|c| contains_ignore_case(c, text))
+            }
+            Expr::Lang { code } => {
+                let lang = text::detect_lang(&article.title, article.content.as_deref());
+                lang.eq_ignore_ascii_case(code)
+            }
             Expr::And { exprs } => exprs.iter().all(|e| e.matches(article)),
             Expr::Or { exprs } => exprs.iter().any(|e| e.matches(article)),
             Expr::Not { expr } => !expr.matches(article),
         }
     }
 
+    /// All tag names referenced by this expression, including the leading
+    /// segments of globs: `tech/*/llm` references `tech`
+    pub fn tags(&self) -> Vec<&str> {
+        match self {
+            Expr::Tag { name } | Expr::Exact { name } => vec![name.as_str()],
+            Expr::Glob { pattern, .. } => match tags::glob_prefix(pattern) {
+                ("", _) => Vec::new(),
+                (prefix, _) => vec![prefix],
+            },
+            Expr::And { exprs } | Expr::Or { exprs } => {
+                exprs.iter().flat_map(|e| e.tags()).collect()
+            }
+            Expr::Not { expr } => expr.tags(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Simplify into an equivalent expression:
+    /// nested And/Or are flattened, duplicates and double negation removed,
+    /// absorption applied (`a AND (a OR b)` is `a`), and contradictions such as
+    /// `a AND NOT a`, `tech/ai AND NOT tech` or `read AND unread` reduced to `NEVER`.
+    pub fn simplify(&self) -> Expr {
+        match self {
+            Expr::Not { expr } => expr.simplify().negate(),
+            Expr::And { exprs } => simplify_n_ary(exprs, true),
+            Expr::Or { exprs } => simplify_n_ary(exprs, false),
+            other => other.clone(),
+        }
+    }
+
+    /// The negation of an expression, without stacking NOTs:
+    /// `NOT a` becomes `a`, `read` becomes `unread`
+    fn negate(self) -> Expr {
+        match self {
+            Expr::Not { expr } => *expr,
+            Expr::Read { read } => Expr::Read { read: !read },
+            Expr::Starred { starred } => Expr::Starred { starred: !starred },
+            e if e == Expr::ALWAYS => Expr::NEVER,
+            e if e == Expr::NEVER => Expr::ALWAYS,
+            e => Expr::Not { expr: Box::new(e) },
+        }
+    }
+
+    /// Check if no article can ever match, e.g. `tech AND NOT tech`
+    pub fn is_never(&self) -> bool {
+        self.simplify() == Expr::NEVER
+    }
+
+    /// Check if every article matching `self` also matches `other`,
+    /// judging by tags alone: `tech/ai` implies `tech`, `NOT tech` implies `NOT tech/ai`
+    fn implies(&self, other: &Expr) -> bool {
+        match (self, other) {
+            _ if self == other => true,
+            (Expr::Tag { name: a } | Expr::Exact { name: a }, Expr::Tag { name: b }) => {
+                tags::is_under(a, b)
+            }
+            (Expr::Not { expr: a }, Expr::Not { expr: b }) => b.implies(a),
+            _ => false,
+        }
+    }
+
+    /// Check if `self` and `other` cannot both match the same article
+    fn excludes(&self, other: &Expr) -> bool {
+        self.implies(&other.clone().negate()) || other.implies(&self.clone().negate())
+    }
+
+    /// Compile to a parameterized SQL condition over the `articles` table.
+    ///
+    /// The condition selects exactly the articles `matches` accepts. Tags are
+    /// looked up in the normalized `article_tags` table; globs, case-insensitive
+    /// text matches and language detection use the SQL functions registered by
+    /// `Database::open`, so the result only runs on a connection opened there.
+    pub fn to_sql(&self) -> SqlFilter {
+        let mut params = Vec::new();
+        let clause = self.sql(&mut params);
+        SqlFilter { clause, params }
+    }
+
+    /// SQL expression ranking articles for `SortOrder::Score`: how many of
+    /// the tags named in this expression an article carries.
+    /// None if the expression names no tags.
+    pub fn score_sql(&self) -> Option<SqlFilter> {
+        let mut names = self.tags();
+        names.sort();
+        names.dedup();
+        let mut params = Vec::new();
+        let terms: Vec<String> = names
+            .into_iter()
+            .map(|name| {
+                let tag = Expr::Tag {
+                    name: name.to_string(),
+                };
+                format!("({})", tag.sql(&mut params))
+            })
+            .collect();
+        if terms.is_empty() {
+            return None;
+        }
+        Some(SqlFilter {
+            clause: terms.join(" + "),
+            params,
+        })
+    }
+
+    /// Every condition evaluates to 0 or 1, never NULL, so NOT behaves like `!`
+    fn sql(&self, params: &mut Vec<Value>) -> String {
+        let has_tag = |cond: &str| {
+            format!(
+                "EXISTS (SELECT 1 FROM article_tags t WHERE t.article_id = articles.id AND {})",
+                cond
+            )
+        };
+        match self {
+            Expr::Tag { name } => {
+                let (low, high) = tags::child_range(name);
+                params.push(Value::Text(name.clone()));
+                params.push(Value::Text(low));
+                params.push(Value::Text(high));
+                has_tag("(t.tag = ? OR (t.tag >= ? AND t.tag < ?))")
+            }
+            Expr::Exact { name } => {
+                params.push(Value::Text(name.clone()));
+                has_tag("t.tag = ?")
+            }
+            Expr::Glob { pattern, exact } => {
+                params.push(Value::Text(pattern.clone()));
+                params.push(Value::Integer(!exact as i64));
+                has_tag("tagrss_glob(?, t.tag, ?)")
+            }
+            Expr::Read { read: true } => "articles.read != 0".to_string(),
+            Expr::Read { read: false } => "articles.read = 0".to_string(),
+            Expr::Starred { starred: true } => "articles.starred != 0".to_string(),
+            Expr::Starred { starred: false } => "articles.starred = 0".to_string(),
+            Expr::Source { name } => {
+                params.push(Value::Text(name.clone()));
+                "tagrss_contains(IFNULL((SELECT title FROM sources s \
+                 WHERE s.id = articles.source_id), ''), ?)"
+                    .to_string()
+            }
+            Expr::Words { cmp, count } => {
+                params.push(Value::Integer(*count as i64));
+                format!("articles.word_count {} ?", cmp.symbol())
+            }
+            Expr::Age { cmp, amount, unit } => {
+                params.push(Value::Integer(*amount as i64 * unit.seconds()));
+                format!(
+                    "IFNULL(unixepoch('now') - unixepoch(articles.published_at) {} ?, 0)",
+                    cmp.symbol()
+                )
+            }
+            Expr::Published { cmp, date } => {
+                params.push(Value::Text(date.format("%Y-%m-%d").to_string()));
+                format!("IFNULL(date(articles.published_at) {} ?, 0)", cmp.symbol())
+            }
+            Expr::Title { text } => {
+                params.push(Value::Text(text.clone()));
+                "tagrss_contains(articles.title, ?)".to_string()
+            }
+            Expr::Text { text } => {
+                params.push(Value::Text(text.clone()));
+                params.push(Value::Text(text.clone()));
+                "(tagrss_contains(articles.title, ?) OR tagrss_contains(IFNULL(articles.content, ''), ?))"
+                    .to_string()
+            }
+            Expr::Lang { code } => {
+                params.push(Value::Text(code.to_ascii_lowercase()));
+                "tagrss_lang(articles.title, articles.content) = ?".to_string()
+            }
+            Expr::And { exprs } if exprs.is_empty() => "1".to_string(),
+            Expr::Or { exprs } if exprs.is_empty() => "0".to_string(),
+            Expr::And { exprs } | Expr::Or { exprs } => {
+                let sep = if matches!(self, Expr::And { .. }) {
+                    " AND "
+                } else {
+                    " OR "
+                };
+                let parts: Vec<String> = exprs
+                    .iter()
+                    .map(|e| format!("({})", e.sql(params)))
+                    .collect();
+                parts.join(sep)
+            }
+            Expr::Not { expr } => format!("NOT ({})", expr.sql(params)),
+        }
+    }
+
     /// Parse a simple expression DSL
     /// Examples:
     ///   "tech"                    -> Tag("tech")
     ///   "tech AND important"      -> And([Tag("tech"), Tag("important")])
     ///   "NOT life"                -> Not(Tag("life"))
     ///   "important AND NOT long"  -> And([Tag("important"), Not(Tag("long"))])
-    pub fn parse(input: &str) -> Result<Expr, String> {
-        let tokens = tokenize(input);
-        if tokens.is_empty() {
-            return Err("Empty expression".to_string());
+    ///
+    ///
+    /// Field predicates (no spaces around the operator):
+    ///   "unread", "read"          -> read state
+    ///   "starred", "unstarred"    -> starred state
+    ///   "source:\"Peter Turchin\"" -> source title contains (case-insensitive)
+    ///   "words>2000"              -> word count (<, <=, >, >=, =)
+    ///   "age<3d"                  -> time since publication (h, d, w)
+    ///   "published>2026-01-01"    -> publication date
+    ///   "title~rust"              -> title contains (case-insensitive)
+    ///   "text~sponsored"          -> title or content contains (case-insensitive)
+    ///   "lang:zh"                 -> detected language
+    ///
+    /// The whole input must be consumed: "tech important" is an error.
+    pub fn parse(input: &str) -> Result<Expr, ParseError> {
+        let tokens = tokenize(input)?;
+        if tokens.len() == 1 {
+            return Err(ParseError::new("empty expression", tokens[0].span, ATOM));
+        }
+        let mut pos = 0;
+        let expr = parse_or(&tokens, &mut pos)?;
+        let t = &tokens[pos];
+        let message = match t.token {
+            Token::Eof => return Ok(expr),
+            Token::RParen => "unmatched ')'".to_string(),
+            _ => format!("unexpected {} after expression", t.token),
+        };
+        Err(ParseError::new(message, t.span, AFTER_OPERAND))
+    }
+}
+
+/// Writes the expression back as filter text with as few parentheses as
+/// possible; parsing the text gives the same tree, as long as no And/Or has
+/// a single operand. `ALWAYS` and `NEVER` are written as `TRUE` and `FALSE`.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Tag { name } => write!(f, "{}", quote_tag(name)),
+            Expr::Exact { name } => write!(f, "={}", quote_tag(name)),
+            Expr::Glob { pattern, exact } => {
+                write!(
+                    f,
+                    "{}{}",
+                    if *exact { "=" } else { "" },
+                    quote_glob(pattern)
+                )
+            }
+            Expr::Read { read } => write!(f, "{}", if *read { "read" } else { "unread" }),
+            Expr::Starred { starred } => {
+                write!(f, "{}", if *starred { "starred" } else { "unstarred" })
+            }
+            Expr::Source { name } => write!(f, "source:{}", quote_value(name)),
+            Expr::Words { cmp, count } => write!(f, "words{}{}", cmp.symbol(), count),
+            Expr::Age { cmp, amount, unit } => {
+                write!(f, "age{}{}{}", cmp.symbol(), amount, unit.suffix())
+            }
+            Expr::Published { cmp, date } => write!(f, "published{}{}", cmp.symbol(), date),
+            Expr::Title { text } => write!(f, "title~{}", quote_value(text)),
+            Expr::Text { text } => write!(f, "text~{}", quote_value(text)),
+            Expr::Lang { code } => write!(f, "lang:{}", quote_value(code)),
+            Expr::And { exprs } if exprs.is_empty() => write!(f, "TRUE"),
+            Expr::Or { exprs } if exprs.is_empty() => write!(f, "FALSE"),
+            Expr::And { exprs } | Expr::Or { exprs } => {
+                let and = matches!(self, Expr::And { .. });
+                for (i, e) in exprs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{}", if and { " AND " } else { " OR " })?;
+                    }
+                    write_operand(f, e, and)?;
+                }
+                Ok(())
+            }
+            Expr::Not { expr } => {
+                write!(f, "NOT ")?;
+                write_operand(f, expr, true)
+            }
+        }
+    }
+}
+
+/// Write an operand of AND (`in_and`), OR or NOT, parenthesized if the
+/// parser would otherwise group it differently
+fn write_operand(f: &mut fmt::Formatter<'_>, expr: &Expr, in_and: bool) -> fmt::Result {
+    // A single-operand And/Or is written as its operand
+    let mut expr = expr;
+    while let Expr::And { exprs } | Expr::Or { exprs } = expr {
+        match exprs.as_slice() {
+            [only] => expr = only,
+            _ => break,
+        }
+    }
+    let wrap = match expr {
+        Expr::And { exprs } => in_and && !exprs.is_empty(),
+        Expr::Or { exprs } => !exprs.is_empty(),
+        _ => false,
+    };
+    if wrap {
+        write!(f, "({})", expr)
+    } else {
+        write!(f, "{}", expr)
+    }
+}
+
+/// Quote a predicate value if it would not read back as a single word
+fn quote_value(value: &str) -> String {
+    let plain = !value.is_empty()
+        && !value
+            .chars()
+            .any(|c| c.is_whitespace() || is_delimiter(c) || c == '"' || c == '\\');
+    if plain {
+        value.to_string()
+    } else {
+        quoted(value)
+    }
+}
+
+/// Wrap text in double quotes, escaping quotes and backslashes
+fn quoted(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Simplify the operands of an And (`and`) or Or
+fn simplify_n_ary(exprs: &[Expr], and: bool) -> Expr {
+    // The constant that decides the result (NEVER for And) and the neutral one
+    let (absorbing, neutral) = if and {
+        (Expr::NEVER, Expr::ALWAYS)
+    } else {
+        (Expr::ALWAYS, Expr::NEVER)
+    };
+
+    let mut flat: Vec<Expr> = Vec::new();
+    for e in exprs.iter().map(Expr::simplify) {
+        let nested = match e {
+            Expr::And { exprs } if and && !exprs.is_empty() => exprs,
+            Expr::Or { exprs } if !and && !exprs.is_empty() => exprs,
+            e => vec![e],
+        };
+        for e in nested {
+            if e == absorbing {
+                return absorbing;
+            }
+            if e != neutral && !flat.contains(&e) {
+                flat.push(e);
+            }
+        }
+    }
+
+    // `a AND NOT a` never matches, and `a OR NOT a` always does
+    for (i, a) in flat.iter().enumerate() {
+        for b in &flat[i + 1..] {
+            let conflict = if and {
+                a.excludes(b)
+            } else {
+                a.clone().negate().excludes(&b.clone().negate())
+            };
+            if conflict {
+                return absorbing;
+            }
+        }
+    }
+
+    // Absorption: drop `a OR b` next to `a` in an And (and vice versa)
+    let absorbed: Vec<bool> = flat
+        .iter()
+        .map(|e| {
+            let inner = match e {
+                Expr::Or { exprs } if and => exprs,
+                Expr::And { exprs } if !and => exprs,
+                _ => return false,
+            };
+            flat.iter().any(|other| other != e && inner.contains(other))
+        })
+        .collect();
+    let mut kept: Vec<Expr> = flat
+        .into_iter()
+        .zip(absorbed)
+        .filter(|(_, absorbed)| !absorbed)
+        .map(|(e, _)| e)
+        .collect();
+
+    match kept.len() {
+        0 => neutral,
+        1 => kept.remove(0),
+        _ if and => Expr::And { exprs: kept },
+        _ => Expr::Or { exprs: kept },
+    }
+}
+
+/// A SQL condition compiled from an `Expr`, with its positional `?` parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct SqlFilter {
+    pub clause: String,
+    pub params: Vec<Value>,
+}
+
+/// Byte range in the filter text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A filter parse error pointing at the offending part of the input
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Span,
+    /// What the parser would have accepted at this position
+    pub expected: Vec<String>,
+}
+
+/// Tokens that can start an operand
+const ATOM: &[&str] = &["tag", "predicate", "NOT", "("];
+/// Tokens that can follow a complete top-level operand
+const AFTER_OPERAND: &[&str] = &["AND", "OR", "end of input"];
+
+impl ParseError {
+    fn new(message: impl Into<String>, span: Span, expected: &[&str]) -> Self {
+        ParseError {
+            message: message.into(),
+            span,
+            expected: expected.iter().map(|s| s.to_string()).collect(),
         }
-        parse_or(&tokens, &mut 0)
+    }
+
+    /// Render the error with the input and a caret line under the offending span:
+    ///
+    /// ```text
+    /// unexpected tag 'important' after expression, expected AND, OR or end of input
+    ///   tech important
+    ///        ^^^^^^^^^
+    /// ```
+    pub fn render(&self, input: &str) -> String {
+        let start = self.span.start.min(input.len());
+        let end = self.span.end.clamp(start, input.len());
+        let pad = input[..start].chars().count();
+        let width = input[start..end].chars().count().max(1);
+        format!(
+            "{}\n  {}\n  {}{}",
+            self,
+            input,
+            " ".repeat(pad),
+            "^".repeat(width)
+        )
     }
 }
 
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        match self.expected.as_slice() {
+            [] => Ok(()),
+            [only] => write!(f, ", expected {}", only),
+            [init @ .., last] => write!(f, ", expected {} or {}", init.join(", "), last),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub(crate) fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
 #[derive(Debug, Clone, PartialEq)]
 enum Token {
-    Tag(String),
+    Tag { name: String, exact: bool },
+    Pred(Expr),
     And,
     Or,
     Not,
     LParen,
     RParen,
+    Eof,
 }
 
-fn tokenize(input: &str) -> Vec<Token> {
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Tag { name, .. } => write!(f, "tag '{}'", name),
+            Token::Pred(_) => write!(f, "predicate"),
+            Token::And => write!(f, "AND"),
+            Token::Or => write!(f, "OR"),
+            Token::Not => write!(f, "NOT"),
+            Token::LParen => write!(f, "'('"),
+            Token::RParen => write!(f, "')'"),
+            Token::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Spanned {
+    token: Token,
+    span: Span,
+}
+
+/// Split the input into tokens with their byte spans, ending with `Token::Eof`
+fn tokenize(input: &str) -> Result<Vec<Spanned>, ParseError> {
     let mut tokens = Vec::new();
-    let mut chars = input.chars().peekable();
+    let mut chars = input.char_indices().peekable();
 
-    while let Some(&c) = chars.peek() {
+    while let Some(&(start, c)) = chars.peek() {
         if c.is_whitespace() {
             chars.next();
             continue;
         }
 
-        if c == '(' {
-            tokens.push(Token::LParen);
+        if is_delimiter(c) {
             chars.next();
+            let mut end = start + 1;
+            let token = match c {
+                '(' => Token::LParen,
+                ')' => Token::RParen,
+                '!' => Token::Not,
+                _ => {
+                    // `&&` and `||` are accepted as well as `&` and `|`
+                    if chars.next_if(|&(_, next)| next == c).is_some() {
+                        end += 1;
+                    }
+                    if c == '&' {
+                        Token::And
+                    } else {
+                        Token::Or
+                    }
+                }
+            };
+            tokens.push(Spanned {
+                token,
+                span: Span { start, end },
+            });
             continue;
         }
 
-        if c == ')' {
-            tokens.push(Token::RParen);
+        // `-tag` is NOT tag
+        if c == '-' {
             chars.next();
-            continue;
+            if chars.peek().is_some_and(|&(_, next)| {
+                next == '(' || !(next.is_whitespace() || is_delimiter(next))
+            }) {
+                let end = start + 1;
+                tokens.push(Spanned {
+                    token: Token::Not,
+                    span: Span { start, end },
+                });
+                continue;
+            }
+            return Err(ParseError::new(
+                "'-' must be followed by an operand",
+                Span {
+                    start,
+                    end: start + 1,
+                },
+                &["tag", "predicate", "("],
+            ));
         }
 
-        // Read a word
-        let mut word = String::new();
-        while let Some(&c) = chars.peek() {
-            if c.is_whitespace() || c == '(' || c == ')' {
+        // Read a word; quoted parts may contain spaces and parentheses
+        let mut end = start;
+        while let Some(&(i, c)) = chars.peek() {
+            if c.is_whitespace() || is_delimiter(c) {
                 break;
             }
-            word.push(c);
             chars.next();
+            end = i + c.len_utf8();
+            if c == '"' {
+                end = skip_quoted(input, i, &mut chars)?;
+            }
+        }
+        let word = &input[start..end];
+        let span = Span { start, end };
+
+        let token = if let Some(rest) = word.strip_prefix('=') {
+            if rest.is_empty() {
+                return Err(ParseError::new("missing tag after '='", span, &["tag"]));
+            }
+            tag_token(rest, true)
+        } else if word.starts_with('"') {
+            tag_token(word, false)
+        } else {
+            match word.to_uppercase().as_str() {
+                "AND" => Token::And,
+                "OR" => Token::Or,
+                "NOT" => Token::Not,
+                "TRUE" => Token::Pred(Expr::ALWAYS),
+                "FALSE" => Token::Pred(Expr::NEVER),
+                _ => match parse_predicate(word, span)? {
+                    Some(pred) => Token::Pred(pred),
+                    None => tag_token(word, false),
+                },
+            }
+        };
+        tokens.push(Spanned {
+            token,
+            span: Span { start, end },
+        });
+    }
+
+    tokens.push(Spanned {
+        token: Token::Eof,
+        span: Span {
+            start: input.len(),
+            end: input.len(),
+        },
+    });
+    Ok(tokens)
+}
+
+fn is_delimiter(c: char) -> bool {
+    matches!(c, '(' | ')' | '&' | '|' | '!')
+}
+
+/// A tag literal: glob (`*` outside quotes), quoted (taken verbatim) or plain
+fn tag_token(word: &str, exact: bool) -> Token {
+    if let Some(pattern) = glob_pattern(word) {
+        Token::Pred(Expr::Glob { pattern, exact })
+    } else if word.starts_with('"') {
+        Token::Tag {
+            name: unquote(word),
+            exact,
         }
+    } else {
+        Token::Tag {
+            name: word.to_string(),
+            exact,
+        }
+    }
+}
 
-        match word.to_uppercase().as_str() {
-            "AND" => tokens.push(Token::And),
-            "OR" => tokens.push(Token::Or),
-            "NOT" => tokens.push(Token::Not),
-            _ => tokens.push(Token::Tag(word)),
+/// The pattern of a word with a `*` outside quotes. Quoted parts are taken
+/// verbatim, so `"my tag"/*` matches the children of `my tag`.
+fn glob_pattern(word: &str) -> Option<String> {
+    let mut pattern = String::with_capacity(word.len());
+    let mut in_quotes = false;
+    let mut wildcard = false;
+    let mut chars = word.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '\\' if in_quotes => pattern.extend(chars.next()),
+            '*' if !in_quotes => {
+                wildcard = true;
+                pattern.push(c);
+            }
+            _ => pattern.push(c),
         }
     }
+    wildcard.then_some(pattern)
+}
 
-    tokens
+/// Write a tag so that it parses back as the same tag, quoting it
+/// if it contains spaces, operators or would read as a keyword or predicate.
+pub fn quote_tag(name: &str) -> String {
+    let plain = matches!(
+        tokenize(name).as_deref(),
+        Ok([Spanned { token: Token::Tag { name: n, exact: false }, .. }, _]) if n == name
+    ) && !name.starts_with('"');
+    if plain {
+        name.to_string()
+    } else {
+        quoted(name)
+    }
 }
 
-fn parse_or(tokens: &[Token], pos: &mut usize) -> Result<Expr, String> {
-    let mut left = parse_and(tokens, pos)?;
+/// Write a glob pattern so that it parses back as the same glob, quoting the
+/// text between the wildcards if the pattern would not read as one word
+fn quote_glob(pattern: &str) -> String {
+    let plain = matches!(
+        tokenize(pattern).as_deref(),
+        Ok([Spanned { token: Token::Pred(Expr::Glob { pattern: p, exact: false }), .. }, _])
+            if p == pattern
+    );
+    if plain {
+        return pattern.to_string();
+    }
+    pattern
+        .split('*')
+        .map(|part| {
+            if part.is_empty() {
+                String::new()
+            } else {
+                quoted(part)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("*")
+}
 
-    while *pos < tokens.len() {
-        if tokens[*pos] == Token::Or {
-            *pos += 1;
-            let right = parse_and(tokens, pos)?;
-            left = match left {
-                Expr::Or { mut exprs } => {
-                    exprs.push(right);
-                    Expr::Or { exprs }
-                }
-                _ => Expr::Or {
-                    exprs: vec![left, right],
-                },
-            };
+/// Advance past a quoted string whose opening quote is at `open`.
+/// Returns the byte offset just after the closing quote.
+fn skip_quoted(
+    input: &str,
+    open: usize,
+    chars: &mut std::iter::Peekable<std::str::CharIndices>,
+) -> Result<usize, ParseError> {
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => return Ok(i + 1),
+            _ => {}
+        }
+    }
+    Err(ParseError::new(
+        "unterminated string",
+        Span {
+            start: open,
+            end: input.len(),
+        },
+        &["'\"'"],
+    ))
+}
+
+/// Remove surrounding quotes and unescape `\"` and `\\`
+fn unquote(value: &str) -> String {
+    let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) else {
+        return value.to_string();
+    };
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(escaped) = chars.next() {
+                out.push(escaped);
+            }
         } else {
-            break;
+            out.push(c);
         }
     }
+    out
+}
+
+/// Field names that take an operator and value
+const FIELDS: &[&str] = &[
+    "source",
+    "words",
+    "age",
+    "published",
+    "title",
+    "text",
+    "lang",
+];
+
+/// Parse a field predicate word like `words>2000`.
+/// Returns None if the word is not a predicate, so it is read as a tag.
+fn parse_predicate(word: &str, span: Span) -> Result<Option<Expr>, ParseError> {
+    match word {
+        "read" => return Ok(Some(Expr::Read { read: true })),
+        "unread" => return Ok(Some(Expr::Read { read: false })),
+        "starred" => return Ok(Some(Expr::Starred { starred: true })),
+        "unstarred" => return Ok(Some(Expr::Starred { starred: false })),
+        _ => {}
+    }
 
-    Ok(left)
+    let Some(op_start) = word.find([':', '~', '<', '>', '=']) else {
+        return Ok(None);
+    };
+    let field = &word[..op_start];
+    if !FIELDS.contains(&field) {
+        return Ok(None);
+    }
+    let rest = &word[op_start..];
+    let (op, value) = ["<=", ">=", ":", "~", "<", ">", "="]
+        .iter()
+        .find_map(|op| rest.strip_prefix(op).map(|v| (*op, v)))
+        .unwrap_or(("", rest));
+    let value = unquote(value);
+
+    let error = |message: String, expected: &[&str]| ParseError::new(message, span, expected);
+    if value.is_empty() {
+        return Err(error(format!("missing value for '{}'", field), &["value"]));
+    }
+    let cmp = match op {
+        "<" => Some(Cmp::Lt),
+        "<=" => Some(Cmp::Le),
+        ">" => Some(Cmp::Gt),
+        ">=" => Some(Cmp::Ge),
+        "=" => Some(Cmp::Eq),
+        _ => None,
+    };
+
+    let expr = match (field, op, cmp) {
+        ("source", ":" | "~" | "=", _) => Expr::Source { name: value },
+        ("title", ":" | "~" | "=", _) => Expr::Title { text: value },
+        ("text", ":" | "~" | "=", _) => Expr::Text { text: value },
+        ("lang", ":" | "=", _) => Expr::Lang { code: value },
+        ("words", _, Some(cmp)) => {
+            let count = value
+                .parse()
+                .map_err(|_| error(format!("invalid word count '{}'", value), &["number"]))?;
+            Expr::Words { cmp, count }
+        }
+        ("age", _, Some(cmp)) => {
+            let (amount, unit) = parse_age(&value).ok_or_else(|| {
+                error(
+                    format!("invalid age '{}'", value),
+                    &["number with unit h, d or w"],
+                )
+            })?;
+            Expr::Age { cmp, amount, unit }
+        }
+        ("published", _, Some(cmp)) => {
+            let date = NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+                .map_err(|_| error(format!("invalid date '{}'", value), &["date YYYY-MM-DD"]))?;
+            Expr::Published { cmp, date }
+        }
+        ("source" | "title" | "text", _, _) => {
+            return Err(error(
+                format!("invalid operator for '{}'", field),
+                &[":", "~"],
+            ))
+        }
+        ("lang", _, _) => return Err(error("invalid operator for 'lang'".to_string(), &[":"])),
+        _ => {
+            return Err(error(
+                format!("invalid operator for '{}'", field),
+                &["<", "<=", ">", ">=", "="],
+            ))
+        }
+    };
+    Ok(Some(expr))
 }
 
-fn parse_and(tokens: &[Token], pos: &mut usize) -> Result<Expr, String> {
-    let mut left = parse_not(tokens, pos)?;
+/// Parse an age value like `12h`, `7d` or `2w`, as in the `age` predicate
+pub fn parse_age(value: &str) -> Option<(u32, AgeUnit)> {
+    let split = value.len() - value.chars().last().map_or(0, |c| c.len_utf8());
+    let unit = match &value[split..] {
+        "h" => AgeUnit::Hours,
+        "d" => AgeUnit::Days,
+        "w" => AgeUnit::Weeks,
+        _ => return None,
+    };
+    let amount = value[..split].parse().ok()?;
+    Some((amount, unit))
+}
 
-    while *pos < tokens.len() {
-        if tokens[*pos] == Token::And {
-            *pos += 1;
-            let right = parse_not(tokens, pos)?;
-            left = match left {
-                Expr::And { mut exprs } => {
-                    exprs.push(right);
-                    Expr::And { exprs }
+/// Rewrite the tag names in filter source text, leaving operators,
+/// parentheses and spacing untouched. Globs have the segments before their
+/// first wildcard rewritten, so renaming `tech/ai` turns `tech/ai/*` into `ai/*`.
+///
+/// New names are written with `quote_tag`, so the result may contain double
+/// quotes and backslashes. It is filter text, not YAML: callers storing it in
+/// a config file must escape it (`config::rewrite_folders_file` does).
+pub fn rewrite_filter_tags(input: &str, rewrite: impl Fn(&str) -> String) -> String {
+    let mut out = String::with_capacity(input.len());
+    let Ok(tokens) = tokenize(input) else {
+        return input.to_string();
+    };
+    let mut last = 0;
+    for t in tokens {
+        let (new_text, exact) = match &t.token {
+            Token::Tag { name, exact } => {
+                let new_name = rewrite(name);
+                if new_name == *name {
+                    continue;
                 }
-                _ => Expr::And {
-                    exprs: vec![left, right],
-                },
-            };
-        } else {
-            break;
+                (quote_tag(&new_name), *exact)
+            }
+            Token::Pred(Expr::Glob { pattern, exact }) => {
+                let (prefix, rest) = tags::glob_prefix(pattern);
+                if prefix.is_empty() {
+                    continue;
+                }
+                let new_prefix = rewrite(prefix);
+                if new_prefix == prefix {
+                    continue;
+                }
+                (quote_glob(&format!("{}{}", new_prefix, rest)), *exact)
+            }
+            _ => continue,
+        };
+        out.push_str(&input[last..t.span.start]);
+        if exact {
+            out.push('=');
         }
+        out.push_str(&new_text);
+        last = t.span.end;
     }
+    out.push_str(&input[last..]);
+    out
+}
 
-    Ok(left)
+fn parse_or(tokens: &[Spanned], pos: &mut usize) -> Result<Expr, ParseError> {
+    let mut exprs = vec![parse_and(tokens, pos)?];
+    while tokens[*pos].token == Token::Or {
+        *pos += 1;
+        exprs.push(parse_and(tokens, pos)?);
+    }
+    Ok(n_ary(exprs, |exprs| Expr::Or { exprs }))
 }
 
-fn parse_not(tokens: &[Token], pos: &mut usize) -> Result<Expr, String> {
-    if *pos < tokens.len() && tokens[*pos] == Token::Not {
+fn parse_and(tokens: &[Spanned], pos: &mut usize) -> Result<Expr, ParseError> {
+    let mut exprs = vec![parse_not(tokens, pos)?];
+    while tokens[*pos].token == Token::And {
         *pos += 1;
-        let expr = parse_atom(tokens, pos)?;
+        exprs.push(parse_not(tokens, pos)?);
+    }
+    Ok(n_ary(exprs, |exprs| Expr::And { exprs }))
+}
+
+/// A single operand stands alone; parenthesized groups stay nested,
+/// so `(a OR b) OR c` keeps its shape
+fn n_ary(mut exprs: Vec<Expr>, make: fn(Vec<Expr>) -> Expr) -> Expr {
+    if exprs.len() == 1 {
+        exprs.remove(0)
+    } else {
+        make(exprs)
+    }
+}
+
+fn parse_not(tokens: &[Spanned], pos: &mut usize) -> Result<Expr, ParseError> {
+    if tokens[*pos].token == Token::Not {
+        *pos += 1;
+        let expr = parse_not(tokens, pos)?;
         Ok(Expr::Not {
             expr: Box::new(expr),
         })
@@ -160,25 +1342,40 @@ fn parse_not(tokens: &[Token], pos: &mut usize) -> Result<Expr, String> {
     }
 }
 
-fn parse_atom(tokens: &[Token], pos: &mut usize) -> Result<Expr, String> {
-    if *pos >= tokens.len() {
-        return Err("Unexpected end of expression".to_string());
-    }
-
-    match &tokens[*pos] {
-        Token::Tag(name) => {
+fn parse_atom(tokens: &[Spanned], pos: &mut usize) -> Result<Expr, ParseError> {
+    let t = &tokens[*pos];
+    match &t.token {
+        Token::Tag { name, exact } => {
+            *pos += 1;
+            let name = name.clone();
+            Ok(if *exact {
+                Expr::Exact { name }
+            } else {
+                Expr::Tag { name }
+            })
+        }
+        Token::Pred(pred) => {
             *pos += 1;
-            Ok(Expr::Tag { name: name.clone() })
+            Ok(pred.clone())
         }
         Token::LParen => {
             *pos += 1;
             let expr = parse_or(tokens, pos)?;
-            if *pos >= tokens.len() || tokens[*pos] != Token::RParen {
-                return Err("Missing closing parenthesis".to_string());
+            let close = &tokens[*pos];
+            if close.token != Token::RParen {
+                return Err(ParseError::new(
+                    format!("missing closing parenthesis, found {}", close.token),
+                    close.span,
+                    &[")", "AND", "OR"],
+                ));
             }
             *pos += 1;
             Ok(expr)
         }
-        _ => Err(format!("Unexpected token: {:?}", tokens[*pos])),
+        other => Err(ParseError::new(
+            format!("unexpected {}", other),
+            t.span,
+            ATOM,
+        )),
     }
 }
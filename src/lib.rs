@@ -1,5 +1,12 @@
+pub mod classify;
 pub mod config;
 pub mod db;
 pub mod feed;
 pub mod folder;
+pub mod migrations;
 pub mod models;
+pub mod retention;
+pub mod settings;
+pub mod suggest;
+pub mod tags;
+pub mod text;
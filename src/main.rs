@@ -1,27 +1,28 @@
-mod config;
-mod db;
-mod feed;
-mod folder;
-mod models;
-
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::{Duration, Local, Utc};
 use clap::{Parser, Subcommand};
-use std::collections::HashSet;
-use std::path::Path;
-
-use db::Database;
-use folder::{Expr, Folder};
-use models::Rule;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::IsTerminal;
+use std::path::PathBuf;
 
-const DB_PATH: &str = "tagrss.db";
-const FEEDS_PATH: &str = "configs/feeds.opml";
-const RULES_PATH: &str = "configs/rules.yaml";
-const FOLDERS_PATH: &str = "configs/folders.yaml";
+use tagrss::classify::{self, Example, NaiveBayes};
+use tagrss::config::FolderEdit;
+use tagrss::db::{ArticleQuery, Database, Period};
+use tagrss::folder::{self, Cmp, Expr, Folder, FolderCount, Grouping, Mute, SortOrder};
+use tagrss::models::{Article, Rule};
+use tagrss::retention::Retention;
+use tagrss::settings::{Paths, Settings};
+use tagrss::tags::{self, TagEdit, TagNode};
+use tagrss::{config, feed, suggest, text};
 
 #[derive(Parser)]
 #[command(name = "tagrss")]
 #[command(about = "Tag-based RSS reader")]
 struct Cli {
+    /// Settings file (default: $TAGRSS_CONFIG, then $XDG_CONFIG_HOME/tagrss/tagrss.toml)
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
     #[command(subcommand)]
     command: Commands,
 }
@@ -42,10 +43,45 @@ enum Commands {
         #[arg(value_delimiter = ',')]
         tags: Vec<String>,
     },
-    /// Sync all sources (fetch new articles)
-    Sync,
+    /// Remove, disable, rename or move a source
+    Source {
+        #[command(subcommand)]
+        cmd: SourceCmd,
+    },
+    /// Sync all enabled sources (fetch new articles)
+    Sync {
+        /// Also sync sources fetched within `sync.min_interval_minutes`
+        #[arg(long)]
+        force: bool,
+    },
     /// List articles, optionally filtered by folder
     List {
+        /// Folder to show; nested folders by path, e.g. Tech/AI
+        #[arg(short, long)]
+        folder: Option<String>,
+        #[arg(short, long, conflicts_with = "all")]
+        unread: bool,
+        /// Include read articles in a folder that shows only unread ones
+        #[arg(short, long)]
+        all: bool,
+        /// Number of articles [default: the folder's limit, or 20]
+        #[arg(short, long)]
+        limit: Option<usize>,
+        /// Sort order: newest, oldest, shortest, score or source
+        #[arg(short, long)]
+        sort: Option<SortOrder>,
+        /// Group by source, day or tag (or none)
+        #[arg(short, long)]
+        group: Option<Grouping>,
+        /// Include articles hidden by the mute list, marked "muted"
+        #[arg(long)]
+        show_muted: bool,
+    },
+    /// Search article titles, content and authors
+    Search {
+        /// Words, "exact phrase", prefix*, AND, OR, NOT and parentheses
+        query: String,
+        /// Only search this folder
         #[arg(short, long)]
         folder: Option<String>,
         #[arg(short, long)]
@@ -53,31 +89,204 @@ enum Commands {
         #[arg(short, long, default_value = "20")]
         limit: usize,
     },
-    /// Show all tags in use
-    Tags,
+    /// Suggest tags for a source (or article) from keywords in its recent articles
+    SuggestTags {
+        /// Source ID, or article ID with --article
+        id: i64,
+        #[arg(long)]
+        article: bool,
+        /// Number of recent articles of the source to analyze
+        #[arg(long, default_value = "20")]
+        recent: usize,
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+    },
+    /// Show the tag tree with article and unread counts
+    Tags {
+        /// Only count articles in this folder
+        #[arg(short, long, conflicts_with = "filter")]
+        folder: Option<String>,
+        /// Only count articles matching this filter expression
+        #[arg(long)]
+        filter: Option<String>,
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show every folder with total, unread and newest article
+    Folders {
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Bulk tag management (rename, merge, delete)
+    Tag {
+        #[command(subcommand)]
+        cmd: TagCmd,
+    },
     /// Rule management
     Rule {
         #[command(subcommand)]
         cmd: RuleCmd,
     },
+    /// Train or evaluate the tag classifier
+    Classify {
+        #[command(subcommand)]
+        cmd: ClassifyCmd,
+    },
     /// Folder management
     Folder {
         #[command(subcommand)]
         cmd: FolderCmd,
     },
-    /// Mark article as read
-    Read { article_id: i64 },
+    /// Show an article's text, marking it read
+    Show {
+        article_id: i64,
+        /// Leave it unread
+        #[arg(long)]
+        keep_unread: bool,
+    },
+    /// Mark an article read (taking it off the read-later queue), or every
+    /// article matching all the given options. Undo with 'tagrss undo'.
+    Read {
+        #[arg(conflicts_with_all = ["folder", "source", "tag", "older_than", "all"])]
+        article_id: Option<i64>,
+        /// Articles in this folder, e.g. Tech/AI
+        #[arg(short, long)]
+        folder: Option<String>,
+        /// Articles from this source ID
+        #[arg(short, long)]
+        source: Option<i64>,
+        /// Articles with this tag or one under it
+        #[arg(short, long)]
+        tag: Option<String>,
+        /// Articles published longer ago than this, e.g. 12h, 7d, 2w
+        #[arg(long)]
+        older_than: Option<String>,
+        /// Every article
+        #[arg(long)]
+        all: bool,
+        /// Mark unread instead
+        #[arg(long)]
+        unread: bool,
+    },
+    /// Undo the last mark-read action
+    Undo,
+    /// Show what was read when
+    History {
+        /// Only articles read in the last N days
+        #[arg(short, long)]
+        days: Option<u32>,
+        /// Maximum number of articles to show
+        #[arg(short, long, default_value = "30")]
+        limit: usize,
+        /// Count articles read per top-level tag instead
+        #[arg(long)]
+        stats: bool,
+        /// Period for --stats: day, week or month
+        #[arg(long, default_value = "week")]
+        by: Period,
+    },
+    /// Star an article to keep it: starred articles are never pruned
+    Star { article_id: i64 },
+    /// Remove the star from an article
+    Unstar { article_id: i64 },
+    /// List starred articles, or export them
+    Starred {
+        /// Export as JSON, with full content
+        #[arg(long, conflicts_with = "markdown")]
+        json: bool,
+        /// Export as a Markdown reading list
+        #[arg(long)]
+        markdown: bool,
+    },
+    /// Show the read-later queue, or change it
+    Later {
+        #[command(subcommand)]
+        cmd: Option<LaterCmd>,
+    },
     /// Import sources from OPML and rules from YAML
     Import {
-        /// OPML file path (default: configs/feeds.opml)
+        /// OPML file path (default: `paths.feeds` from the settings, see `config show`)
+        #[arg(long)]
+        feeds: Option<PathBuf>,
+        /// Rules YAML file path (default: `paths.rules` from the settings, see `config show`)
         #[arg(long)]
-        feeds: Option<String>,
-        /// Rules YAML file path (default: configs/rules.yaml)
+        rules: Option<PathBuf>,
+    },
+    /// Delete old articles according to the retention policies
+    Prune {
+        /// Only list the articles that would be deleted
+        #[arg(long)]
+        dry_run: bool,
+        /// Compact the database file afterwards
         #[arg(long)]
-        rules: Option<String>,
+        vacuum: bool,
     },
     /// Reload folders from YAML config
     Reload,
+    /// Database maintenance
+    Db {
+        #[command(subcommand)]
+        cmd: DbCmd,
+    },
+    /// Settings file and paths
+    Config {
+        #[command(subcommand)]
+        cmd: ConfigCmd,
+    },
+}
+
+#[derive(Subcommand)]
+enum SourceCmd {
+    /// Remove a source and its articles (shows what would be deleted unless --yes)
+    Remove {
+        source_id: i64,
+        /// Keep the articles, no longer linked to any source
+        #[arg(long)]
+        keep_articles: bool,
+        /// Delete without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Stop syncing a source, keeping its articles
+    Disable { source_id: i64 },
+    /// Resume syncing a disabled source
+    Enable { source_id: i64 },
+    /// Change the title shown for a source
+    Rename { source_id: i64, title: String },
+    /// Point a source at a new feed URL, e.g. after it moved
+    SetUrl { source_id: i64, url: String },
+}
+
+#[derive(Subcommand)]
+enum LaterCmd {
+    /// Queue an article, at the end or at a position (1 = read next)
+    Add {
+        article_id: i64,
+        #[arg(long)]
+        position: Option<usize>,
+    },
+    /// Take an article off the queue
+    Remove { article_id: i64 },
+}
+
+#[derive(Subcommand)]
+enum DbCmd {
+    /// Upgrade the database schema (also done automatically on every run)
+    Migrate {
+        /// Only list the migrations that would run
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Compact the database file, returning free space to the OS
+    Vacuum,
+}
+
+#[derive(Subcommand)]
+enum ConfigCmd {
+    /// Print the effective settings, with every path resolved
+    Show,
 }
 
 #[derive(Subcommand)]
@@ -110,14 +319,75 @@ enum RuleCmd {
         #[arg(short, long)]
         tag: String,
     },
+    /// Add a classifier rule (see 'tagrss classify train')
+    AddClassifier {
+        #[arg(short, long)]
+        tag: String,
+        /// Minimum confidence (0-1) for the tag to be added
+        #[arg(long, default_value = "0.8")]
+        threshold: f64,
+    },
     /// Delete a rule
     Delete { rule_id: i64 },
     /// Apply rules to all existing articles
     Apply,
     /// Import rules from YAML file
     Import {
-        #[arg(default_value = "configs/rules.yaml")]
-        path: String,
+        /// Rules YAML file path (default: `paths.rules` from the settings, see `config show`)
+        path: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TagCmd {
+    /// Rename a tag and everything under it (tech/ai -> ai moves tech/ai/llm to ai/llm)
+    Rename {
+        old: String,
+        new: String,
+        /// Also rewrite rules.yaml and folders.yaml
+        #[arg(long)]
+        rewrite_config: bool,
+    },
+    /// Merge one or more tags into another
+    Merge {
+        #[arg(required = true)]
+        tags: Vec<String>,
+        #[arg(long)]
+        into: String,
+        /// Also rewrite rules.yaml and folders.yaml
+        #[arg(long)]
+        rewrite_config: bool,
+    },
+    /// Delete a tag and everything under it
+    Delete { tag: String },
+    /// Tag a single article by hand (kept across 'rule apply')
+    Add {
+        article_id: i64,
+        #[arg(required = true, value_delimiter = ',')]
+        tags: Vec<String>,
+    },
+    /// Remove tags from a single article by hand (kept across 'rule apply')
+    Remove {
+        article_id: i64,
+        #[arg(required = true, value_delimiter = ',')]
+        tags: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ClassifyCmd {
+    /// Train on source and manual tags and store the model in the database
+    Train {
+        /// Ignore tags seen on fewer articles than this
+        #[arg(long, default_value = "3")]
+        min_docs: u32,
+    },
+    /// Train on 80% of articles and report precision/recall on the rest
+    Eval {
+        #[arg(long, default_value = "3")]
+        min_docs: u32,
+        #[arg(long, default_value = "0.8")]
+        threshold: f64,
     },
 }
 
@@ -131,27 +401,222 @@ enum FolderCmd {
         /// Filter expression, e.g. "important AND NOT long"
         filter: String,
     },
-    /// Test a filter expression
-    Test { filter: String },
+    /// Change a folder's filter
+    Edit { name: String, filter: String },
+    /// Rename a folder
+    Rename { old: String, new: String },
+    /// Move a folder to a position in the list (1 = first)
+    Move { name: String, position: usize },
+    /// Delete a folder and the folders nested under it
+    Delete { name: String },
+    /// Test a filter expression, or every folder if none is given
+    Test { filter: Option<String> },
 }
 
-fn load_folders() -> Vec<Folder> {
-    if Path::new(FOLDERS_PATH).exists() {
-        config::load_folders(FOLDERS_PATH).unwrap_or_default()
-    } else {
-        Vec::new()
+fn load_folders(paths: &Paths) -> Vec<Folder> {
+    if !paths.folders.exists() {
+        return Vec::new();
+    }
+    match config::load_folders(&paths.folders) {
+        Ok(folders) => folders,
+        Err(e) => {
+            eprintln!("warning: {:#}", e);
+            Vec::new()
+        }
+    }
+}
+
+fn load_mutes(paths: &Paths) -> Vec<Mute> {
+    if !paths.folders.exists() {
+        return Vec::new();
+    }
+    match config::load_mutes(&paths.folders) {
+        Ok(mutes) => mutes,
+        Err(e) => {
+            eprintln!("warning: {:#}", e);
+            Vec::new()
+        }
+    }
+}
+
+fn load_retention(paths: &Paths) -> Retention {
+    if !paths.retention.exists() {
+        return Retention::default();
+    }
+    match config::load_retention(&paths.retention) {
+        Ok(retention) => retention,
+        Err(e) => {
+            eprintln!("warning: {:#}", e);
+            Retention::default()
+        }
+    }
+}
+
+/// Delete the articles the retention policies select; returns how many
+fn prune(db: &Database, retention: &Retention) -> Result<usize> {
+    let ids: Vec<i64> = db
+        .prune_candidates(retention)?
+        .iter()
+        .map(|c| c.article.id)
+        .collect();
+    db.prune_articles(&ids)
+}
+
+/// Compact the database file, reporting how much smaller it got
+fn vacuum_db(db: &Database, paths: &Paths) -> Result<()> {
+    let size = || fs::metadata(&paths.db).map(|m| m.len() / 1024).ok();
+    let before = size();
+    db.vacuum()?;
+    if let (Some(before), Some(after)) = (before, size()) {
+        println!("Vacuumed: {} KiB -> {} KiB", before, after);
+    }
+    Ok(())
+}
+
+/// Parse a filter expression, rendering errors with a caret under the problem
+fn parse_filter(filter: &str) -> Result<Expr> {
+    Expr::parse(filter).map_err(|e| anyhow::anyhow!(e.render(filter)))
+}
+
+fn print_article_row(a: &Article, muted: bool) {
+    // Manual tags are marked with '*'
+    let tags: Vec<_> = a
+        .tags
+        .iter()
+        .take(3)
+        .map(|t| {
+            if a.manual_tags.contains(t) {
+                format!("{}*", t)
+            } else {
+                t.clone()
+            }
+        })
+        .collect();
+    let read_mark = if a.read { "[x]" } else { "[ ]" };
+    println!(
+        "{:<4} {:<50} {:<20} {}{}{}",
+        a.id,
+        truncate(&a.title, 48),
+        format!("{:?}", tags),
+        read_mark,
+        if a.starred { "  starred" } else { "" },
+        if muted { "  muted" } else { "" }
+    );
+}
+
+/// Starred articles as a Markdown reading list
+fn starred_markdown(articles: &[Article]) -> String {
+    let mut out = String::from("# Starred articles\n\n");
+    for a in articles {
+        out.push_str(&format!("- [{}]({})", a.title.replace(']', "\\]"), a.url));
+        let mut details = Vec::new();
+        if !a.source_title.is_empty() {
+            details.push(a.source_title.clone());
+        }
+        if let Some(published) = a.published_at {
+            details.push(published.format("%Y-%m-%d").to_string());
+        }
+        let mut tags: Vec<_> = a.tags.iter().map(|t| format!("`{}`", t)).collect();
+        tags.sort();
+        details.extend(tags);
+        if !details.is_empty() {
+            out.push_str(&format!(" — {}", details.join(", ")));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Find a folder by path, with its ancestors' filters applied
+fn find_folder(name: &str, paths: &Paths) -> Result<Folder> {
+    folder::resolve(&load_folders(paths), name)
+        .ok_or_else(|| anyhow::anyhow!("No folder named '{}' in {}", name, paths.folders.display()))
+}
+
+/// Articles as classifier examples, keyed by article id
+fn training_examples(db: &Database) -> Result<Vec<(i64, Example)>> {
+    let sources: HashMap<i64, HashSet<String>> = db
+        .get_sources()?
+        .into_iter()
+        .map(|s| (s.id, s.tags))
+        .collect();
+    let manual = db.get_manual_tags()?;
+    let empty = HashSet::new();
+    Ok(db
+        .get_articles()?
+        .iter()
+        .map(|a| {
+            let source_tags = sources.get(&a.source_id).unwrap_or(&empty);
+            let removed = manual.get(&a.id).map_or(&empty, |m| &m.removed);
+            (a.id, Example::from_article(a, source_tags, removed))
+        })
+        .collect())
+}
+
+fn print_tag_tree(nodes: &[TagNode], prefix: &str, root: bool) {
+    for (i, node) in nodes.iter().enumerate() {
+        let (branch, indent) = if root {
+            ("", "")
+        } else if i == nodes.len() - 1 {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        let label = format!("{}{}{}", prefix, branch, node.name);
+        println!("{:<40} {:>6} {:>6}", label, node.total, node.unread);
+        print_tag_tree(&node.children, &format!("{}{}", prefix, indent), false);
+    }
+}
+
+fn print_folder_tree(
+    nodes: &[FolderCount],
+    prefix: &str,
+    root: bool,
+    row: &dyn Fn(&FolderCount) -> String,
+) {
+    for (i, node) in nodes.iter().enumerate() {
+        let (branch, indent) = if root {
+            ("", "")
+        } else if i == nodes.len() - 1 {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        let label = format!("{}{}{}", prefix, branch, node.name);
+        println!("{:<40} {}", label, row(node));
+        print_folder_tree(&node.children, &format!("{}{}", prefix, indent), false, row);
     }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
-    let db = Database::open(DB_PATH)?;
+    let settings = Settings::resolve(cli.config.as_deref())?;
+    let paths = &settings.paths;
+
+    if let Commands::Config {
+        cmd: ConfigCmd::Show,
+    } = cli.command
+    {
+        println!("# Settings from {}", settings.source);
+        print!("{}", settings.to_toml()?);
+        return Ok(());
+    }
+
+    if let Some(dir) = paths.db.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create database directory: {:?}", dir))?;
+    }
+    // `db migrate` shows or applies pending migrations itself
+    let db = match cli.command {
+        Commands::Db { .. } => Database::open_unmigrated(&paths.db)?,
+        _ => Database::open(&paths.db)?,
+    };
 
     match cli.command {
         Commands::Add { url, tags } => {
             println!("Fetching {}...", url);
-            let (title, _) = feed::fetch_feed(&url).await?;
+            let (title, _) = feed::fetch_feed(&settings.http.client()?, &url).await?;
             let tags: HashSet<String> = tags.into_iter().collect();
             let id = db.add_source(&url, &title, &tags)?;
             println!("Added source #{}: {} (tags: {:?})", id, title, tags);
@@ -160,14 +625,24 @@ async fn main() -> Result<()> {
         Commands::Sources => {
             let sources = db.get_sources()?;
             if sources.is_empty() {
-                println!("No sources. Use 'tagrss import' to load from {}", FEEDS_PATH);
+                println!(
+                    "No sources. Use 'tagrss import' to load from {}",
+                    paths.feeds.display()
+                );
                 return Ok(());
             }
-            println!("{:<4} {:<40} {}", "ID", "Title", "Tags");
+            println!("{:<4} {:<40} Tags", "ID", "Title");
             println!("{}", "-".repeat(80));
             for s in sources {
                 let tags: Vec<_> = s.tags.iter().collect();
-                println!("{:<4} {:<40} {:?}", s.id, truncate(&s.title, 38), tags);
+                let disabled = if s.enabled { "" } else { "  (disabled)" };
+                println!(
+                    "{:<4} {:<40} {:?}{}",
+                    s.id,
+                    truncate(&s.title, 38),
+                    tags,
+                    disabled
+                );
             }
         }
 
@@ -177,88 +652,392 @@ async fn main() -> Result<()> {
             println!("Updated source #{} tags to {:?}", source_id, tags);
         }
 
-        Commands::Sync => {
-            let sources = db.get_sources()?;
+        Commands::Source { cmd } => {
+            let source_id = match &cmd {
+                SourceCmd::Remove { source_id, .. }
+                | SourceCmd::Disable { source_id }
+                | SourceCmd::Enable { source_id }
+                | SourceCmd::Rename { source_id, .. }
+                | SourceCmd::SetUrl { source_id, .. } => *source_id,
+            };
+            let source = db
+                .get_source(source_id)?
+                .ok_or_else(|| anyhow::anyhow!("No source #{}", source_id))?;
+            match cmd {
+                SourceCmd::Remove {
+                    keep_articles, yes, ..
+                } => {
+                    let removal = db.source_removal(source.id, keep_articles)?;
+                    println!("Source #{}: {} ({})", source.id, source.title, source.url);
+                    if keep_articles {
+                        println!("  Its articles will be kept without a source");
+                    } else {
+                        println!(
+                            "  {} articles ({} unread, {} starred) and {} manual tags will be deleted",
+                            removal.articles, removal.unread, removal.starred, removal.manual_tags
+                        );
+                    }
+                    if !yes {
+                        println!("Run again with --yes to remove it");
+                        return Ok(());
+                    }
+                    db.remove_source(source.id, keep_articles)?;
+                    println!("Removed source #{}", source.id);
+                }
+                SourceCmd::Disable { .. } => {
+                    db.set_source_enabled(source.id, false)?;
+                    println!("Disabled source #{}: {}", source.id, source.title);
+                }
+                SourceCmd::Enable { .. } => {
+                    db.set_source_enabled(source.id, true)?;
+                    println!("Enabled source #{}: {}", source.id, source.title);
+                }
+                SourceCmd::Rename { title, .. } => {
+                    db.rename_source(source.id, &title)?;
+                    println!(
+                        "Renamed source #{}: {} -> {}",
+                        source.id, source.title, title
+                    );
+                }
+                SourceCmd::SetUrl { url, .. } => {
+                    db.set_source_url(source.id, &url)?;
+                    println!("Source #{} now syncs from {}", source.id, url);
+                }
+            }
+        }
+
+        Commands::Sync { force } => {
+            let sources: Vec<_> = db
+                .get_sources()?
+                .into_iter()
+                .filter(|s| s.enabled)
+                .collect();
             let rules = db.get_rules()?;
+            let model = db.load_classifier()?;
+            let mutes = load_mutes(paths);
             if sources.is_empty() {
-                println!("No sources to sync. Use 'tagrss import' first.");
+                println!("No enabled sources to sync. Use 'tagrss import' first.");
                 return Ok(());
             }
+            let client = settings.http.client()?;
+            let min_interval = Duration::minutes(settings.sync.min_interval_minutes.into());
+            let mut skipped = 0;
             for source in &sources {
+                let recent = source
+                    .last_updated
+                    .is_some_and(|t| Utc::now() - t < min_interval);
+                if recent && !force {
+                    skipped += 1;
+                    continue;
+                }
                 print!("Syncing {}... ", source.title);
-                match feed::sync_source(&db, source, &rules).await {
+                match feed::sync_source(&db, &client, source, &rules, model.as_ref(), &mutes).await
+                {
                     Ok(n) => println!("{} new articles", n),
                     Err(e) => println!("error: {}", e),
                 }
             }
+            if skipped > 0 {
+                println!(
+                    "Skipped {} sources synced in the last {} minutes (use --force to sync them)",
+                    skipped, settings.sync.min_interval_minutes
+                );
+            }
+            let retention = load_retention(paths);
+            if retention.prune_after_sync {
+                println!("Pruned {} old articles", prune(&db, &retention)?);
+            }
+        }
+
+        Commands::Prune { dry_run, vacuum } => {
+            let retention = load_retention(paths);
+            if retention.policies.is_empty() {
+                println!("No retention policies in {}", paths.retention.display());
+                return Ok(());
+            }
+            if dry_run {
+                let candidates = db.prune_candidates(&retention)?;
+                for c in &candidates {
+                    println!(
+                        "{:<4} {:<50} {:<20} {}",
+                        c.article.id,
+                        truncate(&c.article.title, 48),
+                        truncate(&c.article.source_title, 18),
+                        c.policy
+                    );
+                }
+                println!("Dry run: {} articles would be deleted", candidates.len());
+                return Ok(());
+            }
+            println!("Deleted {} articles", prune(&db, &retention)?);
+            if vacuum {
+                vacuum_db(&db, paths)?;
+            }
         }
 
         Commands::List {
             folder,
             unread,
+            all,
             limit,
+            sort,
+            group,
+            show_muted,
         } => {
-            let articles = db.get_articles()?;
-            let folders = load_folders();
+            let folder = folder
+                .as_deref()
+                .map(|name| find_folder(name, paths))
+                .transpose()?;
+            let mute = folder::mute_filter(&load_mutes(paths));
+            // Command-line flags override the folder's view settings
+            let view = folder.as_ref().map(|f| f.view.clone()).unwrap_or_default();
 
-            let filter: Option<Expr> = if let Some(name) = &folder {
-                folders
-                    .iter()
-                    .find(|f| f.name == *name)
-                    .map(|f| f.filter.clone())
-            } else {
-                None
-            };
-
-            let filtered: Vec<_> = articles
-                .into_iter()
-                .filter(|a| !unread || !a.read)
-                .filter(|a| filter.as_ref().map_or(true, |f| f.matches(a)))
-                .take(limit)
-                .collect();
+            let filtered = db.query_articles(&ArticleQuery {
+                filter: folder.as_ref().map(|f| &f.filter),
+                mute: mute.as_ref().filter(|_| !show_muted),
+                source: None,
+                unread: unread || (view.unread && !all),
+                limit: Some(limit.or(view.limit).unwrap_or(20)),
+                sort: sort.unwrap_or(view.sort),
+            })?;
 
             if filtered.is_empty() {
                 println!("No articles found.");
                 return Ok(());
             }
 
-            println!(
-                "{:<4} {:<50} {:<20} {}",
-                "ID", "Title", "Tags", "Read"
-            );
+            let grouping = group.unwrap_or(view.group);
+            println!("{:<4} {:<50} {:<20} Read", "ID", "Title", "Tags");
+            println!("{}", "-".repeat(90));
+            for (i, (heading, articles)) in folder::group_articles(&filtered, grouping)
+                .into_iter()
+                .enumerate()
+            {
+                if grouping != Grouping::None {
+                    if i > 0 {
+                        println!();
+                    }
+                    println!("== {} ({}) ==", heading, articles.len());
+                }
+                for a in articles {
+                    let muted = show_muted && mute.as_ref().is_some_and(|m| m.matches(a));
+                    print_article_row(a, muted);
+                }
+            }
+        }
+
+        Commands::Search {
+            query,
+            folder,
+            unread,
+            limit,
+        } => {
+            let folder = folder
+                .as_deref()
+                .map(|name| find_folder(name, paths))
+                .transpose()?;
+            let mute = folder::mute_filter(&load_mutes(paths));
+            let highlight = if std::io::stdout().is_terminal() {
+                ("\x1b[1m", "\x1b[0m")
+            } else {
+                ("[", "]")
+            };
+            let hits = db.search(
+                &query,
+                &ArticleQuery {
+                    filter: folder.as_ref().map(|f| &f.filter),
+                    mute: mute.as_ref(),
+                    unread,
+                    limit: Some(limit),
+                    ..Default::default()
+                },
+                highlight,
+            )?;
+
+            if hits.is_empty() {
+                println!("No articles found.");
+                return Ok(());
+            }
+            println!("{:<4} {:<50} {:<20} Read", "ID", "Title", "Tags");
             println!("{}", "-".repeat(90));
-            for a in filtered {
-                let tags: Vec<_> = a.tags.iter().take(3).collect();
-                let read_mark = if a.read { "[x]" } else { "[ ]" };
+            for hit in &hits {
+                print_article_row(&hit.article, false);
                 println!(
-                    "{:<4} {:<50} {:<20} {}",
-                    a.id,
-                    truncate(&a.title, 48),
-                    format!("{:?}", tags),
-                    read_mark
+                    "     {}",
+                    hit.snippet.split_whitespace().collect::<Vec<_>>().join(" ")
                 );
             }
         }
 
-        Commands::Tags => {
+        Commands::SuggestTags {
+            id,
+            article,
+            recent,
+            limit,
+        } => {
             let articles = db.get_articles()?;
-            let mut all_tags: HashSet<String> = HashSet::new();
-            for a in &articles {
-                all_tags.extend(a.tags.iter().cloned());
+            let (target, assigned): (Vec<_>, HashSet<String>) = if article {
+                let a = articles
+                    .iter()
+                    .find(|a| a.id == id)
+                    .ok_or_else(|| anyhow::anyhow!("No article #{}", id))?;
+                (vec![a], a.tags.clone())
+            } else {
+                let source = db
+                    .get_source(id)?
+                    .ok_or_else(|| anyhow::anyhow!("No source #{}", id))?;
+                let recent: Vec<_> = articles
+                    .iter()
+                    .filter(|a| a.source_id == id)
+                    .take(recent)
+                    .collect();
+                (recent, source.tags)
+            };
+            if target.is_empty() {
+                println!("No articles to analyze. Run 'tagrss sync' first.");
+                return Ok(());
             }
-            let mut tags: Vec<_> = all_tags.into_iter().collect();
-            tags.sort();
-            println!("Tags in use:");
-            for t in tags {
-                println!("  {}", t);
+
+            let corpus: Vec<_> = articles
+                .iter()
+                .map(|a| suggest::article_terms(&a.title, a.content.as_deref()))
+                .collect();
+            let docs: Vec<_> = target
+                .iter()
+                .map(|a| suggest::article_terms(&a.title, a.content.as_deref()))
+                .collect();
+            let keywords = suggest::keywords(&docs, &corpus, limit * 3);
+
+            let all_tags = tags::with_ancestors(&db.all_tags()?);
+            let assigned = tags::with_ancestors(&assigned);
+            let existing: HashSet<String> = all_tags.difference(&assigned).cloned().collect();
+
+            println!("Analyzed {} articles", target.len());
+            println!("\nExisting tags:");
+            let matched = suggest::match_tags(&keywords, &existing);
+            if matched.is_empty() {
+                println!("  (none)");
+            }
+            for s in matched.iter().take(limit) {
+                println!("  {:<30} {:.3}", s.tag, s.score);
+            }
+            println!("\nNew keywords:");
+            for k in suggest::new_keywords(&keywords, &all_tags).iter().take(limit) {
+                println!("  {:<30} {:.3}", k.term, k.score);
+            }
+        }
+
+        Commands::Tags {
+            folder,
+            filter,
+            json,
+        } => {
+            let expr = match (folder, filter) {
+                (Some(name), _) => Some(find_folder(&name, paths)?.filter),
+                (None, Some(filter)) => Some(parse_filter(&filter)?),
+                (None, None) => None,
+            };
+            let mute = folder::mute_filter(&load_mutes(paths));
+            let tree = tags::build_tree(&db.tag_counts(&ArticleQuery {
+                filter: expr.as_ref(),
+                mute: mute.as_ref(),
+                ..Default::default()
+            })?);
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&tree)?);
+            } else if tree.is_empty() {
+                println!("No tags in use.");
+            } else {
+                println!("{:<40} {:>6} {:>6}", "Tag", "Total", "Unread");
+                println!("{}", "-".repeat(54));
+                print_tag_tree(&tree, "", true);
+            }
+        }
+
+        Commands::Tag { cmd } => {
+            let (edits, rewrite_config) = match cmd {
+                TagCmd::Rename {
+                    old,
+                    new,
+                    rewrite_config,
+                } => {
+                    let in_use = db.all_tags()?;
+                    if !in_use.iter().any(|t| tags::is_under(t, &old)) {
+                        anyhow::bail!("Tag '{}' is not in use", old);
+                    }
+                    if in_use.iter().any(|t| tags::is_under(t, &new)) {
+                        anyhow::bail!(
+                            "Tag '{}' already exists; use 'tagrss tag merge' instead",
+                            new
+                        );
+                    }
+                    let edit = TagEdit::Rename { from: old, to: new };
+                    (vec![edit], rewrite_config)
+                }
+                TagCmd::Merge {
+                    tags,
+                    into,
+                    rewrite_config,
+                } => {
+                    if let Some(t) = tags
+                        .iter()
+                        .find(|t| **t != into && tags::is_under(&into, t))
+                    {
+                        anyhow::bail!("Cannot merge '{}' into its own child '{}'", t, into);
+                    }
+                    let edits = tags
+                        .into_iter()
+                        .filter(|t| *t != into)
+                        .map(|t| TagEdit::Rename {
+                            from: t,
+                            to: into.clone(),
+                        })
+                        .collect();
+                    (edits, rewrite_config)
+                }
+                TagCmd::Delete { tag } => (vec![TagEdit::Delete { tag }], false),
+                TagCmd::Add { article_id, tags } => {
+                    let tags: HashSet<String> = tags.into_iter().collect();
+                    let now = db.set_manual_tags(article_id, &tags, false)?;
+                    println!("Article #{} tags: {:?}", article_id, now);
+                    return Ok(());
+                }
+                TagCmd::Remove { article_id, tags } => {
+                    let tags: HashSet<String> = tags.into_iter().collect();
+                    let now = db.set_manual_tags(article_id, &tags, true)?;
+                    println!("Article #{} tags: {:?}", article_id, now);
+                    return Ok(());
+                }
+            };
+
+            let report = db.rewrite_tags(&edits)?;
+            println!(
+                "Updated {} sources, {} articles, {} rules",
+                report.sources, report.articles, report.rules
+            );
+            for (id, rule) in db.get_rules()? {
+                if report.kept_rules.contains(&id) {
+                    println!(
+                        "  warning: rule #{} still adds '{}', so it comes back on the next sync \
+                         or 'tagrss rule apply'; remove it with 'tagrss rule delete {}'",
+                        id,
+                        rule.tag(),
+                        id
+                    );
+                }
             }
+            report_config_refs(&edits, rewrite_config, paths)?;
         }
 
         Commands::Rule { cmd } => match cmd {
             RuleCmd::List => {
                 let rules = db.get_rules()?;
                 if rules.is_empty() {
-                    println!("No rules defined. Use 'tagrss rule import' to load from {}", RULES_PATH);
+                    println!(
+                        "No rules defined. Use 'tagrss rule import' to load from {}",
+                        paths.rules.display()
+                    );
                     return Ok(());
                 }
                 for (id, rule) in rules {
@@ -300,6 +1079,14 @@ async fn main() -> Result<()> {
                 let id = db.add_rule(&rule)?;
                 println!("Added rule #{} -> tag '{}'", id, tag);
             }
+            RuleCmd::AddClassifier { tag, threshold } => {
+                let rule = Rule::Classifier {
+                    tag: tag.clone(),
+                    threshold,
+                };
+                let id = db.add_rule(&rule)?;
+                println!("Added rule #{} -> tag '{}'", id, tag);
+            }
             RuleCmd::Delete { rule_id } => {
                 db.delete_rule(rule_id)?;
                 println!("Deleted rule #{}", rule_id);
@@ -308,6 +1095,8 @@ async fn main() -> Result<()> {
                 let rules = db.get_rules()?;
                 let sources = db.get_sources()?;
                 let articles = db.get_articles()?;
+                let manual = db.get_manual_tags()?;
+                let model = db.load_classifier()?;
                 let mut updated = 0;
 
                 for article in articles {
@@ -315,11 +1104,15 @@ async fn main() -> Result<()> {
                     let mut tags = source.map(|s| s.tags.clone()).unwrap_or_default();
 
                     for (_, rule) in &rules {
-                        if let Some(tag) = rule.apply(&article) {
+                        if let Some(tag) = rule.apply_with(&article, model.as_ref()) {
                             tags.insert(tag);
                         }
                     }
 
+                    if let Some(overrides) = manual.get(&article.id) {
+                        overrides.apply(&mut tags);
+                    }
+
                     if tags != article.tags {
                         db.update_article_tags(article.id, &tags)?;
                         updated += 1;
@@ -328,8 +1121,9 @@ async fn main() -> Result<()> {
                 println!("Updated {} articles", updated);
             }
             RuleCmd::Import { path } => {
+                let path = path.unwrap_or_else(|| paths.rules.clone());
                 let rules = config::load_rules(&path)?;
-                println!("Loading rules from {}...", path);
+                println!("Loading rules from {}...", path.display());
                 for rule in &rules {
                     db.add_rule(rule)?;
                     println!("  Added: {:?}", rule);
@@ -338,45 +1132,439 @@ async fn main() -> Result<()> {
             }
         },
 
+        Commands::Classify { cmd } => match cmd {
+            ClassifyCmd::Train { min_docs } => {
+                let examples: Vec<_> = training_examples(&db)?
+                    .into_iter()
+                    .map(|(_, e)| e)
+                    .collect();
+                let model = NaiveBayes::train(&examples, min_docs);
+                db.save_classifier(&model)?;
+                let mut known: Vec<_> = model.tags.keys().collect();
+                known.sort();
+                println!(
+                    "Trained on {} articles, {} tags: {:?}",
+                    model.docs,
+                    known.len(),
+                    known
+                );
+            }
+            ClassifyCmd::Eval {
+                min_docs,
+                threshold,
+            } => {
+                // Deterministic split: every fifth article is held out
+                let (test, train): (Vec<_>, Vec<_>) = training_examples(&db)?
+                    .into_iter()
+                    .partition(|(id, _)| id % 5 == 0);
+                let train: Vec<_> = train.into_iter().map(|(_, e)| e).collect();
+                let test: Vec<_> = test.into_iter().map(|(_, e)| e).collect();
+                let model = NaiveBayes::train(&train, min_docs);
+                let scores = classify::evaluate(&model, &test, threshold);
+
+                println!(
+                    "Trained on {} articles, tested on {} (threshold {})",
+                    train.len(),
+                    test.len(),
+                    threshold
+                );
+                println!(
+                    "{:<30} {:>8} {:>10} {:>8}",
+                    "Tag", "Support", "Precision", "Recall"
+                );
+                println!("{}", "-".repeat(59));
+                for s in scores {
+                    println!(
+                        "{:<30} {:>8} {:>10.2} {:>8.2}",
+                        truncate(&s.tag, 28),
+                        s.support(),
+                        s.precision(),
+                        s.recall()
+                    );
+                }
+            }
+        },
+
+        Commands::Folders { json } => {
+            let folders = load_folders(paths);
+            let mute = folder::mute_filter(&load_mutes(paths));
+            let tree = db.folder_counts(&folders, mute.as_ref())?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&tree)?);
+            } else if tree.is_empty() {
+                println!(
+                    "No folders defined. Edit {} to add folders.",
+                    paths.folders.display()
+                );
+            } else {
+                println!("{:<40} {:>6} {:>6}  Newest", "Folder", "Total", "Unread");
+                println!("{}", "-".repeat(70));
+                print_folder_tree(&tree, "", true, &|node| {
+                    let newest = node
+                        .counts
+                        .newest
+                        .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+                        .unwrap_or_else(|| "-".to_string());
+                    format!(
+                        "{:>6} {:>6}  {}",
+                        node.counts.total, node.counts.unread, newest
+                    )
+                });
+            }
+        }
         Commands::Folder { cmd } => match cmd {
             FolderCmd::List => {
-                let folders = load_folders();
+                let folders = load_folders(paths);
                 if folders.is_empty() {
-                    println!("No folders defined. Edit {} to add folders.", FOLDERS_PATH);
+                    println!(
+                        "No folders defined. Edit {} to add folders.",
+                        paths.folders.display()
+                    );
                     return Ok(());
                 }
-                for f in folders {
-                    println!("{}: {:?}", f.name, f.filter);
-                }
+                println!("{:<40} {:>6} {:>6}  Filter", "Folder", "Total", "Unread");
+                println!("{}", "-".repeat(70));
+                let mute = folder::mute_filter(&load_mutes(paths));
+                let tree = db.folder_counts(&folders, mute.as_ref())?;
+                print_folder_tree(&tree, "", true, &|node| {
+                    format!(
+                        "{:>6} {:>6}  {}",
+                        node.counts.total,
+                        node.counts.unread,
+                        node.filter.as_deref().unwrap_or_default()
+                    )
+                });
             }
             FolderCmd::Add { name, filter } => {
-                let _ = Expr::parse(&filter).map_err(|e| anyhow::anyhow!(e))?;
-                println!("To add folder '{}', edit {} and add:", name, FOLDERS_PATH);
-                println!("  - name: {}", name);
-                println!("    filter: {}", filter);
-            }
-            FolderCmd::Test { filter } => {
-                let expr = Expr::parse(&filter).map_err(|e| anyhow::anyhow!(e))?;
-                println!("Parsed: {:?}", expr);
-                let articles = db.get_articles()?;
-                let matching: Vec<_> = articles.iter().filter(|a| expr.matches(a)).collect();
-                println!("Matches {} articles", matching.len());
+                parse_filter(&filter)?;
+                config::edit_folders_file(
+                    &paths.folders,
+                    &FolderEdit::Add {
+                        name: name.clone(),
+                        filter,
+                    },
+                )?;
+                println!("Added folder '{}'", name);
+            }
+            FolderCmd::Edit { name, filter } => {
+                let expr = parse_filter(&filter)?;
+                config::edit_folders_file(
+                    &paths.folders,
+                    &FolderEdit::SetFilter {
+                        name: name.clone(),
+                        filter,
+                    },
+                )?;
+                println!("Folder '{}' now shows: {}", name, expr);
+            }
+            FolderCmd::Rename { old, new } => {
+                config::edit_folders_file(
+                    &paths.folders,
+                    &FolderEdit::Rename {
+                        from: old.clone(),
+                        to: new.clone(),
+                    },
+                )?;
+                println!("Renamed folder '{}' to '{}'", old, new);
+            }
+            FolderCmd::Move { name, position } => {
+                config::edit_folders_file(
+                    &paths.folders,
+                    &FolderEdit::Move {
+                        name: name.clone(),
+                        position: position.saturating_sub(1),
+                    },
+                )?;
+                println!("Moved folder '{}' to position {}", name, position);
+            }
+            FolderCmd::Delete { name } => {
+                config::edit_folders_file(
+                    &paths.folders,
+                    &FolderEdit::Delete { name: name.clone() },
+                )?;
+                println!("Deleted folder '{}'", name);
+            }
+            FolderCmd::Test {
+                filter: Some(filter),
+            } => {
+                let expr = parse_filter(&filter)?;
+                println!("Parsed: {}", expr);
+                let simplified = expr.simplify();
+                if simplified != expr {
+                    println!("Simplified: {}", simplified);
+                }
+                if simplified == Expr::NEVER {
+                    println!("warning: this filter can never match any article");
+                }
+                let counts = db.count_articles(&ArticleQuery {
+                    filter: Some(&expr),
+                    ..Default::default()
+                })?;
+                println!("Matches {} articles", counts.total);
+            }
+            FolderCmd::Test { filter: None } => {
+                let folders = load_folders(paths);
+                if folders.is_empty() {
+                    println!(
+                        "No folders defined. Edit {} to add folders.",
+                        paths.folders.display()
+                    );
+                    return Ok(());
+                }
+                // Nested folders also match their ancestors' filters, so test
+                // each one as it is actually shown
+                let resolved: Vec<Folder> = folders
+                    .iter()
+                    .filter_map(|f| folder::resolve(&folders, &f.name))
+                    .collect();
+                let filters: Vec<Expr> = resolved.iter().map(|f| f.filter.clone()).collect();
+                let counts = db.count_filters(&filters, None)?;
+                let mut dead = 0;
+                for (f, counts) in resolved.iter().zip(&counts) {
+                    println!(
+                        "{:<30} {:>6}  {}",
+                        truncate(&f.name, 30),
+                        counts.total,
+                        f.filter
+                    );
+                    if f.filter.is_never() {
+                        dead += 1;
+                        println!("  warning: folder '{}' can never match any article", f.name);
+                    }
+                }
+                if dead > 0 {
+                    println!("\n{} of {} folders can never match", dead, folders.len());
+                }
+            }
+        },
+
+        Commands::Read {
+            article_id: Some(article_id),
+            unread,
+            ..
+        } => {
+            db.get_article(article_id)?
+                .ok_or_else(|| anyhow::anyhow!("No article #{}", article_id))?;
+            db.mark_read(article_id, !unread)?;
+            if unread {
+                println!("Marked article #{} as unread", article_id);
+            } else {
+                println!("Marked article #{} as read", article_id);
+            }
+        }
+
+        Commands::Read {
+            article_id: None,
+            folder,
+            source,
+            tag,
+            older_than,
+            all,
+            unread,
+        } => {
+            let mut options = Vec::new();
+            let mut filters = Vec::new();
+            if let Some(name) = &folder {
+                filters.push(find_folder(name, paths)?.filter);
+                options.push(format!("--folder {}", name));
+            }
+            if let Some(id) = source {
+                db.get_source(id)?
+                    .ok_or_else(|| anyhow::anyhow!("No source #{}", id))?;
+                options.push(format!("--source {}", id));
+            }
+            if let Some(tag) = &tag {
+                filters.push(Expr::Tag { name: tag.clone() });
+                options.push(format!("--tag {}", tag));
+            }
+            if let Some(age) = &older_than {
+                let (amount, unit) = folder::parse_age(age).ok_or_else(|| {
+                    anyhow::anyhow!("Invalid age '{}': use e.g. 12h, 7d, 2w", age)
+                })?;
+                filters.push(Expr::Age {
+                    cmp: Cmp::Gt,
+                    amount,
+                    unit,
+                });
+                options.push(format!("--older-than {}", age));
+            }
+            if options.is_empty() && !all {
+                anyhow::bail!(
+                    "Give an article ID, or --folder, --source, --tag, --older-than or --all"
+                );
+            }
+            if all {
+                options.push("--all".to_string());
+            }
+
+            let filter = match filters.len() {
+                0 => None,
+                1 => filters.pop(),
+                _ => Some(Expr::And { exprs: filters }),
+            };
+            let state = if unread { "unread" } else { "read" };
+            let changed = db.set_read_matching(
+                &ArticleQuery {
+                    filter: filter.as_ref(),
+                    source,
+                    ..Default::default()
+                },
+                !unread,
+                &format!("mark {} {}", options.join(" "), state),
+            )?;
+            println!("Marked {} articles as {}", changed, state);
+            if changed > 0 {
+                println!("Undo with 'tagrss undo'");
+            }
+        }
+
+        Commands::Show {
+            article_id,
+            keep_unread,
+        } => {
+            let article = db
+                .get_article(article_id)?
+                .ok_or_else(|| anyhow::anyhow!("No article #{}", article_id))?;
+            println!("{}", article.title);
+            let mut byline = vec![article.source_title.clone()];
+            byline.extend(article.author.clone());
+            byline.extend(
+                article
+                    .published_at
+                    .map(|d| d.format("%Y-%m-%d").to_string()),
+            );
+            byline.retain(|s| !s.is_empty());
+            println!("{}", byline.join(" · "));
+            println!("{}", article.url);
+            let mut tags: Vec<_> = article.tags.iter().collect();
+            tags.sort();
+            println!("Tags: {:?}\n", tags);
+            if let Some(content) = &article.content {
+                println!("{}", text::strip_html(content).trim());
+            }
+
+            db.mark_opened(article_id)?;
+            if !keep_unread {
+                db.mark_read(article_id, true)?;
             }
+        }
+
+        Commands::Undo => match db.undo_read()? {
+            Some(action) => println!(
+                "Undid '{}': restored {} articles",
+                action.description, action.articles
+            ),
+            None => println!("Nothing to undo"),
         },
 
-        Commands::Read { article_id } => {
-            db.mark_read(article_id, true)?;
-            println!("Marked article #{} as read", article_id);
+        Commands::History {
+            days,
+            limit,
+            stats,
+            by,
+        } => {
+            let since = days.map(|d| Utc::now() - Duration::days(d as i64));
+            if stats {
+                let stats = db.reading_stats(by, since)?;
+                if stats.is_empty() {
+                    println!("No reading history yet.");
+                }
+                let mut current = None;
+                for s in &stats {
+                    if current != Some(&s.period) {
+                        println!("{}", s.period);
+                        current = Some(&s.period);
+                    }
+                    println!("  {:<30} {:>5}", s.tag, s.read);
+                }
+                return Ok(());
+            }
+
+            let history = db.read_history(since, Some(limit))?;
+            if history.is_empty() {
+                println!("No reading history yet.");
+            }
+            let mut current = None;
+            for entry in &history {
+                let Some(read_at) = entry.read_at else {
+                    continue;
+                };
+                let local = read_at.with_timezone(&Local);
+                let day = local.format("%Y-%m-%d").to_string();
+                if current.as_ref() != Some(&day) {
+                    println!("{}", day);
+                    current = Some(day);
+                }
+                println!(
+                    "  {}  {:<4} {:<50} {}",
+                    local.format("%H:%M"),
+                    entry.article.id,
+                    truncate(&entry.article.title, 48),
+                    truncate(&entry.article.source_title, 24)
+                );
+            }
+        }
+
+        Commands::Star { article_id } | Commands::Unstar { article_id } => {
+            let star = matches!(cli.command, Commands::Star { .. });
+            if !db.set_starred(article_id, star)? {
+                anyhow::bail!("No article #{}", article_id);
+            }
+            let done = if star { "Starred" } else { "Unstarred" };
+            println!("{} article #{}", done, article_id);
+        }
+
+        Commands::Starred { json, markdown } => {
+            let starred = db.starred_articles()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&starred)?);
+            } else if markdown {
+                print!("{}", starred_markdown(&starred));
+            } else if starred.is_empty() {
+                println!("No starred articles. Use 'tagrss star <id>' to keep one.");
+            } else {
+                for a in &starred {
+                    print_article_row(a, false);
+                }
+            }
         }
 
+        Commands::Later { cmd } => match cmd {
+            None => {
+                let queue = db.read_later()?;
+                if queue.is_empty() {
+                    println!("Nothing queued. Use 'tagrss later add <id>'.");
+                }
+                for (i, a) in queue.iter().enumerate() {
+                    print!("{:>3}. ", i + 1);
+                    print_article_row(a, false);
+                }
+            }
+            Some(LaterCmd::Add {
+                article_id,
+                position,
+            }) => {
+                db.get_article(article_id)?
+                    .ok_or_else(|| anyhow::anyhow!("No article #{}", article_id))?;
+                let position = db.enqueue(article_id, position)?;
+                println!("Queued article #{} at position {}", article_id, position);
+            }
+            Some(LaterCmd::Remove { article_id }) => {
+                if !db.dequeue(article_id)? {
+                    anyhow::bail!("Article #{} is not queued", article_id);
+                }
+                println!("Took article #{} off the queue", article_id);
+            }
+        },
+
         Commands::Import { feeds, rules } => {
-            let feeds_path = feeds.as_deref().unwrap_or(FEEDS_PATH);
-            let rules_path = rules.as_deref().unwrap_or(RULES_PATH);
+            let feeds_path = feeds.unwrap_or_else(|| paths.feeds.clone());
+            let rules_path = rules.unwrap_or_else(|| paths.rules.clone());
 
             // Import feeds from OPML
-            if Path::new(feeds_path).exists() {
-                println!("Loading feeds from {}...", feeds_path);
-                let opml_feeds = config::load_opml(feeds_path)?;
+            if feeds_path.exists() {
+                println!("Loading feeds from {}...", feeds_path.display());
+                let opml_feeds = config::load_opml(&feeds_path)?;
 
                 for opml_feed in &opml_feeds {
                     print!("  {} ... ", opml_feed.title);
@@ -387,44 +1575,146 @@ async fn main() -> Result<()> {
                 }
                 println!("Imported {} feeds", opml_feeds.len());
             } else {
-                println!("No {} found, skipping feeds import", feeds_path);
+                println!("No {} found, skipping feeds import", feeds_path.display());
             }
 
             // Import rules from YAML
-            if Path::new(rules_path).exists() {
-                println!("\nLoading rules from {}...", rules_path);
-                let rules = config::load_rules(rules_path)?;
+            if rules_path.exists() {
+                println!("\nLoading rules from {}...", rules_path.display());
+                let rules = config::load_rules(&rules_path)?;
                 for rule in &rules {
                     db.add_rule(rule)?;
                     println!("  Added: {:?}", rule);
                 }
                 println!("Imported {} rules", rules.len());
             } else {
-                println!("No {} found, skipping rules import", rules_path);
+                println!("No {} found, skipping rules import", rules_path.display());
             }
 
             // Check folders
-            if Path::new(FOLDERS_PATH).exists() {
-                let folders = config::load_folders(FOLDERS_PATH)?;
-                println!("\nLoaded {} folders from {}", folders.len(), FOLDERS_PATH);
+            if paths.folders.exists() {
+                let folders = config::load_folders(&paths.folders)?;
+                println!(
+                    "\nLoaded {} folders from {}",
+                    folders.len(),
+                    paths.folders.display()
+                );
             } else {
-                println!("\nNo {} found", FOLDERS_PATH);
+                println!("\nNo {} found", paths.folders.display());
             }
 
             println!("\nDone! Now run 'tagrss sync' to fetch articles.");
         }
 
+        Commands::Db {
+            cmd: DbCmd::Migrate { dry_run },
+        } => {
+            let version = db.schema_version()?;
+            let pending = db.pending_migrations()?;
+            if pending.is_empty() {
+                println!("Schema is up to date (version {})", version);
+                return Ok(());
+            }
+            println!(
+                "Schema version {}, {} migration(s) pending:",
+                version,
+                pending.len()
+            );
+            for m in &pending {
+                println!("  {:>3}  {}", m.version, m.description);
+            }
+            if dry_run {
+                println!("Dry run: nothing applied");
+            } else {
+                db.migrate()?;
+                println!("Migrated to version {}", db.schema_version()?);
+            }
+        }
+
+        Commands::Db { cmd: DbCmd::Vacuum } => {
+            vacuum_db(&db, paths)?;
+        }
+
+        Commands::Config { .. } => unreachable!("handled before opening the database"),
+
         Commands::Reload => {
-            if Path::new(FOLDERS_PATH).exists() {
-                let folders = config::load_folders(FOLDERS_PATH)?;
-                println!("Reloaded {} folders from {}", folders.len(), FOLDERS_PATH);
+            if paths.folders.exists() {
+                let folders = config::load_folders(&paths.folders)?;
+                println!(
+                    "Reloaded {} folders from {}",
+                    folders.len(),
+                    paths.folders.display()
+                );
                 for f in &folders {
-                    println!("  {}: {:?}", f.name, f.filter);
+                    println!("  {}: {}", f.name, f.filter);
+                    if f.filter.is_never() {
+                        println!("    warning: this folder can never match any article");
+                    }
+                }
+                let mutes = config::load_mutes(&paths.folders)?;
+                if !mutes.is_empty() {
+                    println!("Muting {} filter(s):", mutes.len());
+                    for m in &mutes {
+                        let read = if m.mark_read {
+                            " (marked read at sync)"
+                        } else {
+                            ""
+                        };
+                        println!("  {}: {}{}", m.name, m.filter, read);
+                    }
                 }
             } else {
-                println!("No {} found", FOLDERS_PATH);
+                println!("No {} found", paths.folders.display());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Show rules and folders in the YAML configs that reference edited tags,
+/// optionally rewriting them in place.
+fn report_config_refs(edits: &[TagEdit], rewrite: bool, paths: &Paths) -> Result<()> {
+    let affected = |tag: &str| edits.iter().any(|e| e.affects(tag));
+
+    if paths.rules.exists() {
+        for rule in config::load_rules(&paths.rules)? {
+            if affected(rule.tag()) {
+                println!(
+                    "  {} references '{}': {:?}",
+                    paths.rules.display(),
+                    rule.tag(),
+                    rule
+                );
+            }
+        }
+        if rewrite {
+            let n = config::rewrite_rules_file(&paths.rules, edits)?;
+            println!("Rewrote {} rules in {}", n, paths.rules.display());
+        }
+    }
+
+    if paths.folders.exists() {
+        for f in load_folders(paths) {
+            let tags: Vec<_> = f
+                .filter
+                .tags()
+                .into_iter()
+                .filter(|t| affected(t))
+                .collect();
+            if !tags.is_empty() {
+                println!(
+                    "  {} folder '{}' references {:?}",
+                    paths.folders.display(),
+                    f.name,
+                    tags
+                );
             }
         }
+        if rewrite {
+            let n = config::rewrite_folders_file(&paths.folders, edits)?;
+            println!("Rewrote {} folders in {}", n, paths.folders.display());
+        }
     }
 
     Ok(())
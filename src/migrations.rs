@@ -0,0 +1,306 @@
+//! Database schema history.
+//!
+//! The schema version is stored in `PRAGMA user_version`. `Database::open`
+//! applies every migration newer than it, in order, each in its own transaction.
+//!
+//! Databases from before versioning have version 0 but may already contain some
+//! of the early tables, so migrations up to version 4 only use
+//! `IF NOT EXISTS`. Later migrations run exactly once and may alter tables.
+//! Never edit a released migration; add a new one at the end.
+//!
+//! Foreign keys are only enforced after migrating, so a migration may rebuild
+//! a table that others reference.
+
+/// One step in the schema history
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    /// The schema version after this step runs
+    pub version: u32,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "sources, articles and rules",
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS sources (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                url TEXT NOT NULL UNIQUE,
+                title TEXT NOT NULL,
+                tags TEXT NOT NULL DEFAULT '[]',
+                last_updated TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS articles (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                source_id INTEGER NOT NULL REFERENCES sources(id),
+                url TEXT NOT NULL UNIQUE,
+                title TEXT NOT NULL,
+                content TEXT,
+                published_at TEXT,
+                word_count INTEGER NOT NULL DEFAULT 0,
+                tags TEXT NOT NULL DEFAULT '[]',
+                read INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            );
+
+            CREATE TABLE IF NOT EXISTS rules (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                rule_json TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_articles_source ON articles(source_id);
+            CREATE INDEX IF NOT EXISTS idx_articles_read ON articles(read);
+        "#,
+    },
+    Migration {
+        version: 2,
+        description: "manual article tags",
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS manual_tags (
+                article_id INTEGER NOT NULL REFERENCES articles(id),
+                tag TEXT NOT NULL,
+                removed INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (article_id, tag)
+            );
+        "#,
+    },
+    Migration {
+        version: 3,
+        description: "stored tag classifier",
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS classifier (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                model_json TEXT NOT NULL,
+                trained_at TEXT NOT NULL
+            );
+        "#,
+    },
+    Migration {
+        version: 4,
+        description: "article_tags index table kept in sync by triggers",
+        sql: r#"
+            -- One row per article tag, kept in sync with articles.tags by triggers
+            CREATE TABLE IF NOT EXISTS article_tags (
+                article_id INTEGER NOT NULL REFERENCES articles(id),
+                tag TEXT NOT NULL,
+                PRIMARY KEY (article_id, tag)
+            );
+
+            CREATE TRIGGER IF NOT EXISTS article_tags_insert AFTER INSERT ON articles BEGIN
+                INSERT OR IGNORE INTO article_tags (article_id, tag)
+                SELECT new.id, value FROM json_each(new.tags);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS article_tags_update AFTER UPDATE OF tags ON articles BEGIN
+                DELETE FROM article_tags WHERE article_id = old.id;
+                INSERT OR IGNORE INTO article_tags (article_id, tag)
+                SELECT new.id, value FROM json_each(new.tags);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS article_tags_delete AFTER DELETE ON articles BEGIN
+                DELETE FROM article_tags WHERE article_id = old.id;
+            END;
+
+            INSERT OR IGNORE INTO article_tags (article_id, tag)
+            SELECT a.id, j.value FROM articles a, json_each(a.tags) j;
+
+            CREATE INDEX IF NOT EXISTS idx_articles_published ON articles(published_at);
+            CREATE INDEX IF NOT EXISTS idx_article_tags_tag ON article_tags(tag);
+        "#,
+    },
+    Migration {
+        version: 5,
+        description: "source_tags table and covering tag prefix indexes",
+        sql: r#"
+            -- One row per source tag, kept in sync with sources.tags by triggers
+            CREATE TABLE source_tags (
+                source_id INTEGER NOT NULL REFERENCES sources(id),
+                tag TEXT NOT NULL,
+                PRIMARY KEY (source_id, tag)
+            );
+
+            CREATE TRIGGER source_tags_insert AFTER INSERT ON sources BEGIN
+                INSERT OR IGNORE INTO source_tags (source_id, tag)
+                SELECT new.id, value FROM json_each(new.tags);
+            END;
+
+            CREATE TRIGGER source_tags_update AFTER UPDATE OF tags ON sources BEGIN
+                DELETE FROM source_tags WHERE source_id = old.id;
+                INSERT OR IGNORE INTO source_tags (source_id, tag)
+                SELECT new.id, value FROM json_each(new.tags);
+            END;
+
+            CREATE TRIGGER source_tags_delete AFTER DELETE ON sources BEGIN
+                DELETE FROM source_tags WHERE source_id = old.id;
+            END;
+
+            INSERT INTO source_tags (source_id, tag)
+            SELECT DISTINCT s.id, j.value FROM sources s, json_each(s.tags) j;
+
+            -- Hierarchical lookups are range scans over the tag ("tech" to "tech0"),
+            -- answered from the index alone when it also holds the id
+            DROP INDEX idx_article_tags_tag;
+            CREATE INDEX idx_article_tags_tag ON article_tags(tag, article_id);
+            CREATE INDEX idx_source_tags_tag ON source_tags(tag, source_id);
+        "#,
+    },
+    Migration {
+        version: 6,
+        description: "article authors and full-text search index",
+        sql: r#"
+            ALTER TABLE articles ADD COLUMN author TEXT;
+
+            -- Search index over plain text, one row per article (rowid = articles.id).
+            -- The triggers call tagrss_strip_html, so articles can only be written
+            -- through a connection opened by `Database`.
+            CREATE VIRTUAL TABLE articles_fts USING fts5(
+                title, content, author,
+                tokenize = 'unicode61 remove_diacritics 2'
+            );
+
+            CREATE TRIGGER articles_fts_insert AFTER INSERT ON articles BEGIN
+                INSERT INTO articles_fts (rowid, title, content, author)
+                VALUES (new.id, new.title, tagrss_strip_html(new.content), new.author);
+            END;
+
+            CREATE TRIGGER articles_fts_update AFTER UPDATE OF title, content, author ON articles BEGIN
+                DELETE FROM articles_fts WHERE rowid = old.id;
+                INSERT INTO articles_fts (rowid, title, content, author)
+                VALUES (new.id, new.title, tagrss_strip_html(new.content), new.author);
+            END;
+
+            CREATE TRIGGER articles_fts_delete AFTER DELETE ON articles BEGIN
+                DELETE FROM articles_fts WHERE rowid = old.id;
+            END;
+
+            INSERT INTO articles_fts (rowid, title, content, author)
+            SELECT id, title, tagrss_strip_html(content), author FROM articles;
+        "#,
+    },
+    Migration {
+        version: 7,
+        description: "disabled sources, articles kept after their source is removed",
+        sql: r#"
+            ALTER TABLE sources ADD COLUMN enabled INTEGER NOT NULL DEFAULT 1;
+
+            -- Rebuild articles so source_id can become NULL when a source is removed
+            -- but its articles are kept. Ids are preserved, so article_tags,
+            -- manual_tags and the search index stay valid.
+            CREATE TABLE articles_new (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                source_id INTEGER REFERENCES sources(id) ON DELETE SET NULL,
+                url TEXT NOT NULL UNIQUE,
+                title TEXT NOT NULL,
+                content TEXT,
+                published_at TEXT,
+                word_count INTEGER NOT NULL DEFAULT 0,
+                tags TEXT NOT NULL DEFAULT '[]',
+                read INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                author TEXT
+            );
+
+            INSERT INTO articles_new
+                (id, source_id, url, title, content, published_at, word_count, tags, read,
+                 created_at, author)
+            SELECT id, source_id, url, title, content, published_at, word_count, tags, read,
+                   created_at, author
+            FROM articles;
+
+            DROP TABLE articles;
+            ALTER TABLE articles_new RENAME TO articles;
+
+            CREATE INDEX idx_articles_source ON articles(source_id);
+            CREATE INDEX idx_articles_read ON articles(read);
+            CREATE INDEX idx_articles_published ON articles(published_at);
+
+            CREATE TRIGGER article_tags_insert AFTER INSERT ON articles BEGIN
+                INSERT OR IGNORE INTO article_tags (article_id, tag)
+                SELECT new.id, value FROM json_each(new.tags);
+            END;
+
+            CREATE TRIGGER article_tags_update AFTER UPDATE OF tags ON articles BEGIN
+                DELETE FROM article_tags WHERE article_id = old.id;
+                INSERT OR IGNORE INTO article_tags (article_id, tag)
+                SELECT new.id, value FROM json_each(new.tags);
+            END;
+
+            CREATE TRIGGER article_tags_delete AFTER DELETE ON articles BEGIN
+                DELETE FROM article_tags WHERE article_id = old.id;
+            END;
+
+            CREATE TRIGGER articles_fts_insert AFTER INSERT ON articles BEGIN
+                INSERT INTO articles_fts (rowid, title, content, author)
+                VALUES (new.id, new.title, tagrss_strip_html(new.content), new.author);
+            END;
+
+            CREATE TRIGGER articles_fts_update AFTER UPDATE OF title, content, author ON articles BEGIN
+                DELETE FROM articles_fts WHERE rowid = old.id;
+                INSERT INTO articles_fts (rowid, title, content, author)
+                VALUES (new.id, new.title, tagrss_strip_html(new.content), new.author);
+            END;
+
+            CREATE TRIGGER articles_fts_delete AFTER DELETE ON articles BEGIN
+                DELETE FROM articles_fts WHERE rowid = old.id;
+            END;
+        "#,
+    },
+    Migration {
+        version: 8,
+        description: "URLs of pruned articles, so sync does not fetch them again",
+        sql: r#"
+            CREATE TABLE pruned_urls (
+                url TEXT PRIMARY KEY,
+                pruned_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            ) WITHOUT ROWID;
+        "#,
+    },
+    Migration {
+        version: 9,
+        description: "starred articles and the read-later queue",
+        sql: r#"
+            ALTER TABLE articles ADD COLUMN starred INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE articles ADD COLUMN starred_at TEXT;
+            CREATE INDEX idx_articles_starred ON articles(starred_at) WHERE starred = 1;
+
+            -- Articles queued for later, in reading order (position 1 first)
+            CREATE TABLE read_later (
+                article_id INTEGER PRIMARY KEY REFERENCES articles(id) ON DELETE CASCADE,
+                position INTEGER NOT NULL
+            );
+        "#,
+    },
+    Migration {
+        version: 10,
+        description: "read and opened times, undo log for mark-read actions",
+        sql: r#"
+            ALTER TABLE articles ADD COLUMN read_at TEXT;
+            ALTER TABLE articles ADD COLUMN opened_at TEXT;
+            CREATE INDEX idx_articles_read_at ON articles(read_at) WHERE read_at IS NOT NULL;
+
+            -- Each mark-read action, with the previous state of the articles it
+            -- changed, so it can be undone
+            CREATE TABLE read_actions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                description TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE read_action_articles (
+                action_id INTEGER NOT NULL REFERENCES read_actions(id) ON DELETE CASCADE,
+                article_id INTEGER NOT NULL REFERENCES articles(id) ON DELETE CASCADE,
+                was_read INTEGER NOT NULL,
+                was_read_at TEXT,
+                PRIMARY KEY (action_id, article_id)
+            );
+        "#,
+    },
+];
+
+/// The schema version this build creates and understands
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
@@ -2,6 +2,9 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
+use crate::classify::NaiveBayes;
+use crate::text;
+
 /// A feed source with its associated tags
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Source {
@@ -10,20 +13,27 @@ pub struct Source {
     pub title: String,
     pub tags: HashSet<String>, // Tags inherited by all articles
     pub last_updated: Option<DateTime<Utc>>,
+    pub enabled: bool, // Disabled sources are skipped by sync
 }
 
 /// An article from a feed
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Article {
     pub id: i64,
-    pub source_id: i64,
+    pub source_id: i64, // 0 once the source is removed with its articles kept
+    pub source_title: String, // Title of the source, for display and `source:` filters
     pub url: String,
     pub title: String,
     pub content: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
     pub published_at: Option<DateTime<Utc>>,
     pub word_count: u32,
-    pub tags: HashSet<String>, // Inherited from source + rule-added
+    pub tags: HashSet<String>, // Inherited from source + rule-added + manual
+    pub manual_tags: HashSet<String>, // Added by hand with `tag add`
     pub read: bool,
+    #[serde(default)]
+    pub starred: bool,
 }
 
 impl Article {
@@ -55,6 +65,21 @@ impl Article {
     }
 }
 
+/// Tags added or removed by hand on a single article
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ManualTags {
+    pub added: HashSet<String>,
+    pub removed: HashSet<String>,
+}
+
+impl ManualTags {
+    /// Apply manual overrides on top of automatic (source + rule) tags
+    pub fn apply(&self, tags: &mut HashSet<String>) {
+        tags.retain(|t| !self.removed.contains(t));
+        tags.extend(self.added.iter().cloned());
+    }
+}
+
 /// A tagging rule
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -78,10 +103,45 @@ pub enum Rule {
         min_days: Option<u32>,
         tag: String,
     },
+    /// Add tag if the trained classifier predicts it with enough confidence
+    Classifier {
+        tag: String,
+        #[serde(default = "default_threshold")]
+        threshold: f64,
+    },
+}
+
+pub fn default_threshold() -> f64 {
+    0.8
 }
 
 impl Rule {
+    /// The tag this rule adds
+    pub fn tag(&self) -> &str {
+        match self {
+            Rule::Contains { tag, .. }
+            | Rule::WordCount { tag, .. }
+            | Rule::Age { tag, .. }
+            | Rule::Classifier { tag, .. } => tag,
+        }
+    }
+
+    pub fn set_tag(&mut self, new_tag: String) {
+        match self {
+            Rule::Contains { tag, .. }
+            | Rule::WordCount { tag, .. }
+            | Rule::Age { tag, .. }
+            | Rule::Classifier { tag, .. } => *tag = new_tag,
+        }
+    }
+
     pub fn apply(&self, article: &Article) -> Option<String> {
+        self.apply_with(article, None)
+    }
+
+    /// Like `apply`, with the trained classifier available to `Classifier` rules.
+    /// Classifier rules never match without a model.
+    pub fn apply_with(&self, article: &Article, model: Option<&NaiveBayes>) -> Option<String> {
         match self {
             Rule::Contains {
                 pattern,
@@ -106,8 +166,8 @@ impl Rule {
             }
             Rule::WordCount { min, max, tag } => {
                 let wc = article.word_count;
-                let above_min = min.map_or(true, |m| wc >= m);
-                let below_max = max.map_or(true, |m| wc <= m);
+                let above_min = min.is_none_or(|m| wc >= m);
+                let below_max = max.is_none_or(|m| wc <= m);
                 if above_min && below_max {
                     Some(tag.clone())
                 } else {
@@ -119,18 +179,25 @@ impl Rule {
                 min_days,
                 tag,
             } => {
-                let Some(pub_date) = article.published_at else {
-                    return None;
-                };
+                let pub_date = article.published_at?;
                 let age_days = (Utc::now() - pub_date).num_days() as u32;
-                let recent_enough = max_days.map_or(true, |m| age_days <= m);
-                let old_enough = min_days.map_or(true, |m| age_days >= m);
+                let recent_enough = max_days.is_none_or(|m| age_days <= m);
+                let old_enough = min_days.is_none_or(|m| age_days >= m);
                 if recent_enough && old_enough {
                     Some(tag.clone())
                 } else {
                     None
                 }
             }
+            Rule::Classifier { tag, threshold } => {
+                let tokens = text::article_tokens(&article.title, article.content.as_deref());
+                let confidence = model?.confidence(&tokens, tag)?;
+                if confidence >= *threshold {
+                    Some(tag.clone())
+                } else {
+                    None
+                }
+            }
         }
     }
 }
@@ -0,0 +1,28 @@
+//! Retention policies: which stored articles `tagrss prune` deletes.
+//!
+//! Each policy applies to the articles matching its filter and deletes those
+//! past its age limit or beyond its per-source cap. An article is deleted if
+//! any policy deletes it, unless it matches the `keep` filter. Starred articles
+//! and those queued to read later are always kept.
+
+use crate::folder::Expr;
+
+#[derive(Debug, Clone)]
+pub struct Policy {
+    pub name: String,
+    /// Articles this policy applies to
+    pub filter: Expr,
+    /// Delete articles fetched and published more than this many days ago
+    pub max_days: Option<u32>,
+    /// Keep only the newest this many articles of each source
+    pub max_per_source: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Retention {
+    pub policies: Vec<Policy>,
+    /// Articles matching this are never deleted, nor counted towards a cap
+    pub keep: Option<Expr>,
+    /// Prune after every `tagrss sync`
+    pub prune_after_sync: bool,
+}
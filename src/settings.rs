@@ -0,0 +1,261 @@
+//! Where tagrss keeps its database and config files, and how it fetches feeds.
+//!
+//! Settings are read from `tagrss.toml`, the first found of:
+//! 1. the `--config` flag
+//! 2. `$TAGRSS_CONFIG`
+//! 3. `$XDG_CONFIG_HOME/tagrss/tagrss.toml` (default `~/.config/tagrss/tagrss.toml`)
+//!
+//! Every key is optional. Relative paths in the file are relative to its
+//! directory, and the YAML and OPML files default to sitting next to it.
+//!
+//! Without a file, the database lives in `$XDG_DATA_HOME/tagrss` (default
+//! `~/.local/share/tagrss`) and the other files in the XDG config directory,
+//! unless the current directory holds a `tagrss.db` or a `configs/` directory:
+//! then the old layout of `tagrss.db` and `configs/` in the current directory
+//! is kept. `tagrss config show` prints which of these applies.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Environment variable naming the config file
+pub const CONFIG_ENV: &str = "TAGRSS_CONFIG";
+const CONFIG_FILE: &str = "tagrss.toml";
+const DB_FILE: &str = "tagrss.db";
+/// Config directory of the old layout, next to `tagrss.db`
+const LEGACY_CONFIG_DIR: &str = "configs";
+
+/// Database and config file locations, all absolute
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Paths {
+    pub db: PathBuf,
+    pub feeds: PathBuf,
+    pub rules: PathBuf,
+    pub folders: PathBuf,
+    pub retention: PathBuf,
+}
+
+impl Paths {
+    /// The database in `data_dir`, the other files in `config_dir`
+    fn in_dirs(data_dir: &Path, config_dir: &Path) -> Self {
+        Paths {
+            db: data_dir.join(DB_FILE),
+            feeds: config_dir.join("feeds.opml"),
+            rules: config_dir.join("rules.yaml"),
+            folders: config_dir.join("folders.yaml"),
+            retention: config_dir.join("retention.yaml"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpSettings {
+    /// Seconds before a feed request is given up
+    pub timeout_secs: u64,
+    pub user_agent: String,
+    /// Proxy for every request, e.g. `http://localhost:3128`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        HttpSettings {
+            timeout_secs: 30,
+            user_agent: format!("tagrss/{}", env!("CARGO_PKG_VERSION")),
+            proxy: None,
+        }
+    }
+}
+
+impl HttpSettings {
+    /// An HTTP client for fetching feeds with these settings
+    pub fn client(&self) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder()
+            .timeout(Duration::from_secs(self.timeout_secs))
+            .user_agent(&self.user_agent);
+        if let Some(proxy) = &self.proxy {
+            let proxy =
+                reqwest::Proxy::all(proxy).with_context(|| format!("Invalid proxy: {}", proxy))?;
+            builder = builder.proxy(proxy);
+        }
+        Ok(builder.build()?)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SyncSettings {
+    /// Skip sources synced less than this many minutes ago (`sync --force` ignores it)
+    pub min_interval_minutes: u32,
+}
+
+/// Where the effective settings came from
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingsSource {
+    /// The `--config` flag
+    Flag(PathBuf),
+    /// `$TAGRSS_CONFIG`
+    Env(PathBuf),
+    /// The XDG config directory
+    Xdg(PathBuf),
+    /// No file; a `tagrss.db` or `configs/` in the current directory
+    CurrentDir,
+    /// No file; XDG directories
+    Defaults,
+}
+
+impl SettingsSource {
+    /// The config file read, if any
+    pub fn file(&self) -> Option<&Path> {
+        match self {
+            SettingsSource::Flag(p) | SettingsSource::Env(p) | SettingsSource::Xdg(p) => Some(p),
+            SettingsSource::CurrentDir | SettingsSource::Defaults => None,
+        }
+    }
+}
+
+impl fmt::Display for SettingsSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsSource::Flag(p) => write!(f, "{} (--config)", p.display()),
+            SettingsSource::Env(p) => write!(f, "{} (${})", p.display(), CONFIG_ENV),
+            SettingsSource::Xdg(p) => write!(f, "{}", p.display()),
+            SettingsSource::CurrentDir => {
+                write!(
+                    f,
+                    "defaults, using {} and {}/ in the current directory",
+                    DB_FILE, LEGACY_CONFIG_DIR
+                )
+            }
+            SettingsSource::Defaults => write!(f, "defaults, no {} found", CONFIG_FILE),
+        }
+    }
+}
+
+/// The effective settings
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Settings {
+    #[serde(skip)]
+    pub source: SettingsSource,
+    pub paths: Paths,
+    pub http: HttpSettings,
+    pub sync: SyncSettings,
+}
+
+/// `tagrss.toml` as written
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SettingsFile {
+    paths: PathsFile,
+    http: HttpSettings,
+    sync: SyncSettings,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PathsFile {
+    db: Option<PathBuf>,
+    feeds: Option<PathBuf>,
+    rules: Option<PathBuf>,
+    folders: Option<PathBuf>,
+    retention: Option<PathBuf>,
+}
+
+impl Settings {
+    /// Find and load the settings for this process
+    pub fn resolve(flag: Option<&Path>) -> Result<Self> {
+        let cwd = std::env::current_dir()?;
+        Self::resolve_with(flag, &|key| std::env::var(key).ok(), &cwd)
+    }
+
+    /// Like `resolve`, reading environment variables with `env` and
+    /// relative paths from `cwd`
+    pub fn resolve_with(
+        flag: Option<&Path>,
+        env: &dyn Fn(&str) -> Option<String>,
+        cwd: &Path,
+    ) -> Result<Self> {
+        let var = |key: &str| env(key).filter(|v| !v.is_empty());
+        let home = var("HOME").map(PathBuf::from);
+        // Relative XDG directories are ignored, as the spec asks
+        let xdg_dir = |key: &str, fallback: &str| {
+            var(key)
+                .map(PathBuf::from)
+                .filter(|p| p.is_absolute())
+                .or_else(|| home.as_ref().map(|h| h.join(fallback)))
+                .unwrap_or_else(|| cwd.to_path_buf())
+                .join("tagrss")
+        };
+        let config_dir = xdg_dir("XDG_CONFIG_HOME", ".config");
+        let data_dir = xdg_dir("XDG_DATA_HOME", ".local/share");
+
+        let source = if let Some(path) = flag {
+            SettingsSource::Flag(cwd.join(path))
+        } else if let Some(path) = var(CONFIG_ENV) {
+            SettingsSource::Env(cwd.join(path))
+        } else if config_dir.join(CONFIG_FILE).exists() {
+            SettingsSource::Xdg(config_dir.join(CONFIG_FILE))
+        } else if cwd.join(DB_FILE).exists() || cwd.join(LEGACY_CONFIG_DIR).is_dir() {
+            SettingsSource::CurrentDir
+        } else {
+            SettingsSource::Defaults
+        };
+
+        let (file, paths) = match &source {
+            SettingsSource::CurrentDir => (
+                SettingsFile::default(),
+                Paths::in_dirs(cwd, &cwd.join(LEGACY_CONFIG_DIR)),
+            ),
+            SettingsSource::Defaults => (
+                SettingsFile::default(),
+                Paths::in_dirs(&data_dir, &config_dir),
+            ),
+            _ => {
+                let path = source.file().expect("file source");
+                let content = fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read config file: {:?}", path))?;
+                let file: SettingsFile = toml::from_str(&content)
+                    .with_context(|| format!("Failed to parse config file: {:?}", path))?;
+                let base = path.parent().unwrap_or(cwd);
+                let defaults = Paths::in_dirs(&data_dir, base);
+                let place = |value: &Option<PathBuf>, default: PathBuf| match value {
+                    Some(p) => expand_path(p, base, home.as_deref()),
+                    None => default,
+                };
+                let paths = Paths {
+                    db: place(&file.paths.db, defaults.db),
+                    feeds: place(&file.paths.feeds, defaults.feeds),
+                    rules: place(&file.paths.rules, defaults.rules),
+                    folders: place(&file.paths.folders, defaults.folders),
+                    retention: place(&file.paths.retention, defaults.retention),
+                };
+                (file, paths)
+            }
+        };
+
+        Ok(Settings {
+            source,
+            paths,
+            http: file.http,
+            sync: file.sync,
+        })
+    }
+
+    /// The settings as `tagrss.toml` text, with every path resolved
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+}
+
+/// Expand a leading `~/` to the home directory and make the path absolute
+fn expand_path(path: &Path, base: &Path, home: Option<&Path>) -> PathBuf {
+    match (path.strip_prefix("~"), home) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => base.join(path),
+    }
+}
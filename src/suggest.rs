@@ -0,0 +1,118 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::text;
+
+/// A keyword with its TF-IDF score
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyword {
+    pub term: String,
+    pub score: f64,
+}
+
+/// An existing tag that matches the extracted keywords
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagSuggestion {
+    pub tag: String,
+    pub score: f64,
+}
+
+/// Terms of an article's title and (HTML-stripped) content for keyword
+/// extraction. Unlike `text::article_tokens`, two-letter words are kept so
+/// they can match tags like "ai" or "ml".
+pub fn article_terms(title: &str, content: Option<&str>) -> Vec<String> {
+    let mut terms = text::keyword_tokens(title);
+    if let Some(content) = content {
+        terms.extend(text::keyword_tokens(&text::strip_html(content)));
+    }
+    terms
+}
+
+/// Extract the top keywords of `docs` by TF-IDF against `corpus`.
+///
+/// Term frequency is summed over all target documents and normalized by their
+/// total length; document frequency comes from the corpus (which should
+/// include the target documents).
+pub fn keywords(docs: &[Vec<String>], corpus: &[Vec<String>], limit: usize) -> Vec<Keyword> {
+    let mut df: HashMap<&str, u32> = HashMap::new();
+    for doc in corpus {
+        let unique: HashSet<&str> = doc.iter().map(|t| t.as_str()).collect();
+        for term in unique {
+            *df.entry(term).or_default() += 1;
+        }
+    }
+
+    let mut tf: HashMap<&str, u32> = HashMap::new();
+    let mut total = 0;
+    for doc in docs {
+        for term in doc {
+            *tf.entry(term.as_str()).or_default() += 1;
+            total += 1;
+        }
+    }
+    if total == 0 {
+        return Vec::new();
+    }
+
+    let n = corpus.len() as f64;
+    let mut scored: Vec<Keyword> = tf
+        .into_iter()
+        .map(|(term, count)| {
+            let idf = ((n + 1.0) / (df.get(term).copied().unwrap_or(0) as f64 + 1.0)).ln() + 1.0;
+            Keyword {
+                term: term.to_string(),
+                score: count as f64 / total as f64 * idf,
+            }
+        })
+        .collect();
+    scored.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.term.cmp(&b.term))
+    });
+    scored.truncate(limit);
+    scored
+}
+
+/// Words in a tag's last segment, e.g. "tech/machine-learning" -> ["machine", "learning"].
+/// Short words are kept, since tags like "ai" or "ml" are often nothing else.
+fn tag_terms(tag: &str) -> Vec<String> {
+    let last = tag.rsplit('/').next().unwrap_or(tag);
+    text::words(last)
+}
+
+/// Rank existing tags by how well their words match the keywords.
+///
+/// A tag scores the keyword scores of its matching words, averaged over its words,
+/// so "machine-learning" needs both words to rank as high as "ml" matching "ml".
+pub fn match_tags(keywords: &[Keyword], tags: &HashSet<String>) -> Vec<TagSuggestion> {
+    let scores: HashMap<&str, f64> = keywords
+        .iter()
+        .map(|k| (k.term.as_str(), k.score))
+        .collect();
+
+    let mut matched: Vec<TagSuggestion> = tags
+        .iter()
+        .filter_map(|tag| {
+            let terms = tag_terms(tag);
+            if terms.is_empty() {
+                return None;
+            }
+            let sum: f64 = terms.iter().filter_map(|t| scores.get(t.as_str())).sum();
+            (sum > 0.0).then(|| TagSuggestion {
+                tag: tag.clone(),
+                score: sum / terms.len() as f64,
+            })
+        })
+        .collect();
+    matched.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.tag.cmp(&b.tag)));
+    matched
+}
+
+/// Keywords that are not already a word of any existing tag
+pub fn new_keywords<'a>(keywords: &'a [Keyword], tags: &HashSet<String>) -> Vec<&'a Keyword> {
+    let known: HashSet<String> = tags.iter().flat_map(|t| tag_terms(t)).collect();
+    keywords
+        .iter()
+        .filter(|k| !known.contains(&k.term))
+        .collect()
+}
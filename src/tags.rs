@@ -0,0 +1,208 @@
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Check if `tag` is `ancestor` itself or one of its hierarchical children.
+///
+/// "tech/ai/llm" is under "tech" and "tech/ai", but not under "ai" or "technology".
+pub fn is_under(tag: &str, ancestor: &str) -> bool {
+    tag == ancestor
+        || (tag.len() > ancestor.len()
+            && tag.starts_with(ancestor)
+            && tag.as_bytes()[ancestor.len()] == b'/')
+}
+
+/// Bounds of the tags strictly under `tag` in byte order: every "tag/..."
+/// sorts at or after "tag/" and before "tag0" ('0' follows '/').
+pub fn child_range(tag: &str) -> (String, String) {
+    (format!("{}/", tag), format!("{}0", tag))
+}
+
+/// Expand tags with all their ancestors: {"tech/ai/llm"} -> {"tech", "tech/ai", "tech/ai/llm"}
+pub fn with_ancestors(tags: &HashSet<String>) -> HashSet<String> {
+    let mut all = HashSet::new();
+    for tag in tags {
+        for (i, c) in tag.char_indices() {
+            if c == '/' {
+                all.insert(tag[..i].to_string());
+            }
+        }
+        all.insert(tag.clone());
+    }
+    all
+}
+
+/// A bulk edit applied to every occurrence of a tag (and its children)
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagEdit {
+    /// Move `from` and everything under it to `to`
+    /// e.g. tech/ai -> ai turns tech/ai/llm into ai/llm
+    Rename { from: String, to: String },
+    /// Remove `tag` and everything under it
+    Delete { tag: String },
+}
+
+impl TagEdit {
+    /// The tag this edit operates on
+    pub fn target(&self) -> &str {
+        match self {
+            TagEdit::Rename { from, .. } => from,
+            TagEdit::Delete { tag } => tag,
+        }
+    }
+
+    /// Check if a tag is touched by this edit
+    pub fn affects(&self, tag: &str) -> bool {
+        is_under(tag, self.target())
+    }
+
+    /// Rewrite a single tag. Returns None if the tag is deleted,
+    /// and the tag unchanged if the edit does not affect it.
+    pub fn rewrite(&self, tag: &str) -> Option<String> {
+        if !self.affects(tag) {
+            return Some(tag.to_string());
+        }
+        match self {
+            TagEdit::Rename { from, to } => Some(format!("{}{}", to, &tag[from.len()..])),
+            TagEdit::Delete { .. } => None,
+        }
+    }
+
+    /// Rewrite a set of tags. Renamed tags that collide are merged.
+    pub fn apply(&self, tags: &HashSet<String>) -> HashSet<String> {
+        tags.iter().filter_map(|t| self.rewrite(t)).collect()
+    }
+}
+
+/// Apply edits in order to a set of tags
+pub fn apply_all(edits: &[TagEdit], tags: &HashSet<String>) -> HashSet<String> {
+    edits
+        .iter()
+        .fold(tags.clone(), |acc, edit| edit.apply(&acc))
+}
+
+/// Counts of rows changed by a bulk tag edit
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TagEditReport {
+    pub sources: usize,
+    pub articles: usize,
+    pub rules: usize,
+    /// Rules that add a deleted tag. They are kept, so the tag comes back
+    /// the next time rules are applied.
+    pub kept_rules: Vec<i64>,
+}
+
+/// Number of articles carrying a tag or any of its children
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub total: u32,
+    pub unread: u32,
+}
+
+/// A node in the tag hierarchy with counts that include all descendants
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TagNode {
+    /// Last path segment, e.g. "llm" for tech/ai/llm
+    pub name: String,
+    /// Full tag path
+    pub tag: String,
+    pub total: u32,
+    pub unread: u32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TagNode>,
+}
+
+/// Build a tag tree from flat counts.
+///
+/// Counts are expected to already include descendants (as returned by
+/// `Database::tag_counts`). Missing intermediate levels are created with zero counts.
+pub fn build_tree(counts: &[TagCount]) -> Vec<TagNode> {
+    let mut sorted: Vec<&TagCount> = counts.iter().collect();
+    sorted.sort_by(|a, b| a.tag.cmp(&b.tag));
+
+    let mut roots = Vec::new();
+    for count in sorted {
+        let segments: Vec<&str> = count.tag.split('/').collect();
+        insert_node(&mut roots, &segments, 1, count);
+    }
+    roots
+}
+
+fn insert_node(nodes: &mut Vec<TagNode>, segments: &[&str], depth: usize, count: &TagCount) {
+    let name = segments[depth - 1];
+    let idx = match nodes.iter().position(|n| n.name == name) {
+        Some(idx) => idx,
+        None => {
+            nodes.push(TagNode {
+                name: name.to_string(),
+                tag: segments[..depth].join("/"),
+                total: 0,
+                unread: 0,
+                children: Vec::new(),
+            });
+            nodes.len() - 1
+        }
+    };
+    let node = &mut nodes[idx];
+    if depth == segments.len() {
+        node.total = count.total;
+        node.unread = count.unread;
+    } else {
+        insert_node(&mut node.children, segments, depth + 1, count);
+    }
+}
+
+/// Split a glob pattern into its leading segments without wildcards and the
+/// rest: `tech/ai/*` gives ("tech/ai", "/*"), `*/rust` gives ("", "*/rust").
+pub fn glob_prefix(pattern: &str) -> (&str, &str) {
+    let wild = pattern.find('*').unwrap_or(pattern.len());
+    let split = pattern[..wild].rfind('/').unwrap_or(0);
+    pattern.split_at(split)
+}
+
+/// Match a tag against a glob pattern, segment by segment.
+///
+/// `*` matches any characters within one segment (`tech/*`, `*/rust`, `ll*`),
+/// `**` matches any number of whole segments (`**/rust`).
+/// If `hierarchical`, the pattern may also match just a leading part of the tag,
+/// like plain tag queries: `tech/*` matches "tech/ai/llm".
+pub fn glob_match(pattern: &str, tag: &str, hierarchical: bool) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let segments: Vec<&str> = tag.split('/').collect();
+    if hierarchical {
+        (1..=segments.len()).any(|n| match_segments(&pattern, &segments[..n]))
+    } else {
+        match_segments(&pattern, &segments)
+    }
+}
+
+fn match_segments(pattern: &[&str], segments: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => segments.is_empty(),
+        Some((&"**", rest)) => (0..=segments.len()).any(|i| match_segments(rest, &segments[i..])),
+        Some((p, rest)) => match segments.split_first() {
+            Some((s, tail)) => wildcard_match(p, s) && match_segments(rest, tail),
+            None => false,
+        },
+    }
+}
+
+/// Match a single segment where `*` stands for any run of characters
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !text.starts_with(first) || text.len() < first.len() + last.len() || !text.ends_with(last) {
+        return false;
+    }
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
@@ -0,0 +1,177 @@
+/// Common English words that carry no topic information
+const STOPWORDS: &[&str] = &[
+    "an", "as", "at", "be", "by", "do", "he", "if", "in", "is", "it", "me", "my", "no", "of", "on",
+    "or", "so", "to", "up", "us", "we", "about", "after", "all", "also", "and", "any", "are",
+    "because", "been", "before", "but", "can", "could", "did", "does", "for", "from", "had", "has",
+    "have", "her", "his", "how", "into", "its", "just", "more", "most", "not", "now", "one",
+    "only", "other", "our", "out", "over", "she", "should", "some", "such", "than", "that", "the",
+    "their", "them", "then", "there", "these", "they", "this", "those", "through", "very", "was",
+    "were", "what", "when", "where", "which", "while", "who", "why", "will", "with", "would",
+    "you", "your",
+];
+
+/// Strip HTML tags, keeping only the text between them
+pub fn strip_html(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                out.push(' ');
+            }
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}'   // Hiragana, Katakana
+        | '\u{3400}'..='\u{4dbf}' // CJK Extension A
+        | '\u{4e00}'..='\u{9fff}' // CJK Unified Ideographs
+        | '\u{ac00}'..='\u{d7af}' // Hangul
+    )
+}
+
+/// Split text into lowercase word tokens.
+///
+/// Words shorter than 3 characters, numbers and stopwords are dropped.
+/// CJK text has no spaces, so each CJK character becomes its own token.
+pub fn tokenize(text: &str) -> Vec<String> {
+    split_words(text, |word| {
+        word.chars().count() >= 3
+            && !word.chars().all(|c| c.is_ascii_digit())
+            && !STOPWORDS.contains(&word)
+    })
+}
+
+/// Like `tokenize`, but keeping two-letter words such as "ai" or "go",
+/// which are common as tags
+pub fn keyword_tokens(text: &str) -> Vec<String> {
+    split_words(text, |word| {
+        word.chars().count() >= 2
+            && !word.chars().all(|c| c.is_ascii_digit())
+            && !STOPWORDS.contains(&word)
+    })
+}
+
+/// Split text into lowercase words like `tokenize`, keeping short words,
+/// numbers and stopwords
+pub fn words(text: &str) -> Vec<String> {
+    split_words(text, |_| true)
+}
+
+fn split_words(text: &str, keep: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let push_word = |tokens: &mut Vec<String>, word: &mut String| {
+        if !word.is_empty() && keep(word) {
+            tokens.push(word.clone());
+        }
+        word.clear();
+    };
+
+    for c in text.chars() {
+        if is_cjk(c) {
+            push_word(&mut tokens, &mut word);
+            tokens.push(c.to_string());
+        } else if c.is_alphanumeric() {
+            word.extend(c.to_lowercase());
+        } else {
+            push_word(&mut tokens, &mut word);
+        }
+    }
+    push_word(&mut tokens, &mut word);
+
+    tokens
+}
+
+/// Tokens from an article's title and (HTML-stripped) content
+pub fn article_tokens(title: &str, content: Option<&str>) -> Vec<String> {
+    let mut tokens = tokenize(title);
+    if let Some(content) = content {
+        tokens.extend(tokenize(&strip_html(content)));
+    }
+    tokens
+}
+
+/// Frequent function words used to tell Latin-script languages apart
+const LANG_MARKERS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &["the", "and", "of", "to", "is", "that", "with", "for"],
+    ),
+    (
+        "de",
+        &["der", "die", "und", "das", "ist", "nicht", "mit", "ein"],
+    ),
+    (
+        "fr",
+        &["le", "la", "les", "et", "est", "des", "une", "pour"],
+    ),
+    (
+        "es",
+        &["el", "los", "las", "que", "por", "una", "para", "con"],
+    ),
+];
+
+/// Guess an article's language as an ISO 639-1 code from its title and content.
+///
+/// CJK, Cyrillic and other scripts are recognized by character ranges; Latin-script
+/// text is scored on common function words. Returns "und" if nothing matches.
+pub fn detect_lang(title: &str, content: Option<&str>) -> &'static str {
+    let mut body = title.to_string();
+    if let Some(content) = content {
+        body.push(' ');
+        body.push_str(&strip_html(content));
+    }
+
+    let (mut han, mut kana, mut hangul, mut cyrillic, mut latin) = (0, 0, 0, 0, 0);
+    for c in body.chars() {
+        match c {
+            '\u{3040}'..='\u{30ff}' => kana += 1,
+            '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' => han += 1,
+            '\u{ac00}'..='\u{d7af}' => hangul += 1,
+            '\u{0400}'..='\u{04ff}' => cyrillic += 1,
+            c if c.is_ascii_alphabetic() || ('\u{00c0}'..='\u{024f}').contains(&c) => latin += 1,
+            _ => {}
+        }
+    }
+
+    // Japanese mixes kana with kanji, so any notable kana wins over Han
+    let cjk = han + kana + hangul;
+    if cjk > 0 && cjk >= latin / 4 {
+        return if kana * 10 >= cjk {
+            "ja"
+        } else if hangul > han {
+            "ko"
+        } else {
+            "zh"
+        };
+    }
+    if cyrillic > latin {
+        return "ru";
+    }
+    if latin == 0 {
+        return "und";
+    }
+
+    let lower = body.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .collect();
+    LANG_MARKERS
+        .iter()
+        .map(|(code, markers)| {
+            let hits = words.iter().filter(|w| markers.contains(w)).count();
+            (*code, hits)
+        })
+        .filter(|(_, hits)| *hits > 0)
+        .max_by_key(|(_, hits)| *hits)
+        .map_or("und", |(code, _)| code)
+}
@@ -0,0 +1,226 @@
+use std::collections::HashSet;
+use std::io::Write;
+use tagrss::classify::{evaluate, Example, NaiveBayes};
+use tagrss::config::load_rules;
+use tagrss::db::Database;
+use tagrss::models::{Article, Rule};
+use tagrss::text::{strip_html, tokenize};
+use tempfile::{NamedTempFile, TempDir};
+
+fn example(text: &str, tags: &[&str]) -> Example {
+    Example {
+        tokens: tokenize(text),
+        tags: tags.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
+fn corpus() -> Vec<Example> {
+    vec![
+        example("neural network training with transformers", &["ai"]),
+        example("large language model transformers benchmark", &["ai"]),
+        example("training neural language models on GPUs", &["ai"]),
+        example("election results and parliament coalition", &["politics"]),
+        example("parliament votes on the new budget", &["politics"]),
+        example("coalition talks after the election", &["politics"]),
+    ]
+}
+
+// ==================== Tokenizer tests ====================
+
+#[test]
+fn test_tokenize() {
+    let tokens = tokenize("The Rust compiler, version 1.75, is FAST!");
+    assert_eq!(tokens, vec!["rust", "compiler", "version", "fast"]);
+}
+
+#[test]
+fn test_tokenize_cjk() {
+    let tokens = tokenize("学习 Rust");
+    assert_eq!(tokens, vec!["学", "习", "rust"]);
+}
+
+#[test]
+fn test_strip_html() {
+    let text = strip_html("<p>Hello <b>world</b></p>");
+    assert_eq!(
+        text.split_whitespace().collect::<Vec<_>>(),
+        vec!["Hello", "world"]
+    );
+}
+
+// ==================== Naive Bayes tests ====================
+
+#[test]
+fn test_train_and_confidence() {
+    let model = NaiveBayes::train(&corpus(), 1);
+    let ai = model
+        .confidence(&tokenize("transformers for language models"), "ai")
+        .unwrap();
+    let politics = model
+        .confidence(&tokenize("transformers for language models"), "politics")
+        .unwrap();
+    assert!(ai > 0.5);
+    assert!(politics < 0.5);
+    assert!(model.confidence(&[], "unknown").is_none());
+}
+
+#[test]
+fn test_train_min_docs() {
+    let model = NaiveBayes::train(&corpus(), 4);
+    assert!(model.tags.is_empty());
+}
+
+#[test]
+fn test_predict_sorted() {
+    let model = NaiveBayes::train(&corpus(), 1);
+    let predicted = model.predict(&tokenize("parliament election coalition"), 0.5);
+    assert_eq!(predicted[0].0, "politics");
+}
+
+#[test]
+fn test_evaluate() {
+    let model = NaiveBayes::train(&corpus(), 1);
+    let test = vec![
+        example("neural transformers", &["ai"]),
+        example("parliament budget", &["politics"]),
+    ];
+    let scores = evaluate(&model, &test, 0.5);
+    assert_eq!(scores.len(), 2);
+    for s in scores {
+        assert_eq!(s.support(), 1);
+        assert_eq!(s.precision(), 1.0);
+        assert_eq!(s.recall(), 1.0);
+    }
+}
+
+#[test]
+fn test_example_from_article_uses_chosen_tags() {
+    let article = Article {
+        id: 1,
+        source_id: 1,
+        source_title: String::new(),
+        url: "http://example.com".to_string(),
+        title: "Transformers".to_string(),
+        content: Some("<p>neural networks</p>".to_string()),
+        author: None,
+        published_at: None,
+        word_count: 2,
+        tags: ["long".to_string()].into(),
+        manual_tags: ["tech/ai".to_string()].into(),
+        read: false,
+        starred: false,
+    };
+    let source_tags: HashSet<String> = ["news".to_string()].into();
+    let e = Example::from_article(&article, &source_tags, &HashSet::new());
+    let expected: HashSet<String> = ["news", "tech", "tech/ai"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    assert_eq!(e.tags, expected);
+    assert_eq!(e.tokens, vec!["transformers", "neural", "networks"]);
+}
+
+#[test]
+fn test_example_from_article_leaves_out_removed_tags() {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("test.db")).unwrap();
+    let source_tags: HashSet<String> = ["news".to_string(), "tech/ai".to_string()].into();
+    let source = db
+        .add_source("http://example.com/feed", "Example", &source_tags)
+        .unwrap();
+    let id = db
+        .add_article(&Article {
+            id: 0,
+            source_id: source,
+            source_title: String::new(),
+            url: "http://example.com/a".to_string(),
+            title: "Elections".to_string(),
+            content: None,
+            author: None,
+            published_at: None,
+            word_count: 1,
+            tags: source_tags.clone(),
+            manual_tags: HashSet::new(),
+            read: false,
+            starred: false,
+        })
+        .unwrap();
+    db.set_manual_tags(id, &["tech/ai".to_string()].into(), true)
+        .unwrap();
+
+    let manual = db.get_manual_tags().unwrap();
+    let article = &db.get_articles().unwrap()[0];
+    let e = Example::from_article(article, &source_tags, &manual[&id].removed);
+    let expected: HashSet<String> = ["news".to_string()].into();
+    assert_eq!(e.tags, expected);
+}
+
+#[test]
+fn test_db_classifier_roundtrip() {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("test.db")).unwrap();
+    assert!(db.load_classifier().unwrap().is_none());
+
+    let model = NaiveBayes::train(
+        &[Example {
+            tokens: vec!["rust".to_string()],
+            tags: ["tech".to_string()].into(),
+        }],
+        1,
+    );
+    db.save_classifier(&model).unwrap();
+    db.save_classifier(&model).unwrap();
+    let loaded = db.load_classifier().unwrap().unwrap();
+    assert_eq!(loaded.docs, 1);
+    assert!(loaded.tags.contains_key("tech"));
+}
+
+// ==================== Classifier rule tests ====================
+
+#[test]
+fn test_rule_classifier() {
+    let model = NaiveBayes::train(&corpus(), 1);
+    let article = Article {
+        id: 1,
+        source_id: 1,
+        source_title: String::new(),
+        url: "http://example.com".to_string(),
+        title: "Training transformers".to_string(),
+        content: Some("neural language models".to_string()),
+        author: None,
+        published_at: None,
+        word_count: 3,
+        tags: HashSet::new(),
+        manual_tags: HashSet::new(),
+        read: false,
+        starred: false,
+    };
+    let rule = Rule::Classifier {
+        tag: "ai".to_string(),
+        threshold: 0.6,
+    };
+    assert_eq!(
+        rule.apply_with(&article, Some(&model)),
+        Some("ai".to_string())
+    );
+    assert_eq!(rule.apply(&article), None);
+}
+
+#[test]
+fn test_load_classifier_rule() {
+    let yaml = r#"
+rules:
+  - type: classifier
+    tag: tech/ai
+    threshold: 0.9
+  - type: classifier
+    tag: politics
+"#;
+
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(yaml.as_bytes()).unwrap();
+
+    let rules = load_rules(file.path()).unwrap();
+    assert!(matches!(&rules[0], Rule::Classifier { threshold, .. } if *threshold == 0.9));
+    assert!(matches!(&rules[1], Rule::Classifier { threshold, .. } if *threshold == 0.8));
+}
@@ -1,6 +1,13 @@
+use std::fs;
 use std::io::Write;
-use tempfile::NamedTempFile;
-use tagrss::config::{load_folders, load_opml, load_rules};
+use std::path::{Path, PathBuf};
+use tagrss::config::{
+    edit_folders_file, load_folders, load_mutes, load_opml, load_retention, load_rules,
+    rewrite_folders_file, FolderEdit, FolderErrors,
+};
+use tagrss::folder::{Expr, FolderView, Grouping, SortOrder};
+use tagrss::tags::TagEdit;
+use tempfile::{NamedTempFile, TempDir};
 
 #[test]
 fn test_load_opml() {
@@ -82,3 +89,329 @@ folders:
     assert_eq!(folders[0].name, "Priority");
     assert_eq!(folders[1].name, "AI News");
 }
+
+#[test]
+fn test_load_folders_reports_all_errors() {
+    let yaml = r#"
+folders:
+  - name: Good
+    filter: tech
+  - name: Trailing
+    filter: tech important
+  - name: Dangling
+    filter: tech AND
+"#;
+
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(yaml.as_bytes()).unwrap();
+
+    let err = load_folders(file.path()).unwrap_err();
+    let errors = err.downcast_ref::<FolderErrors>().unwrap();
+    assert_eq!(errors.errors.len(), 2);
+    assert_eq!(errors.errors[0].name, "Trailing");
+    assert_eq!(errors.errors[1].name, "Dangling");
+    assert!(err.to_string().contains("^^^^^^^^^"));
+}
+
+// ==================== Folder editing tests ====================
+
+const FOLDERS_YAML: &str = "# My folders\nfolders:\n  # Top priority\n  - name: Priority\n    filter: important AND NOT long # keep short\n\n  - name: AI News\n    filter: tech/ai\n\n  - name: Leisure\n    filter: life\n\n# end\n";
+
+fn folders_file(content: &str) -> (TempDir, PathBuf) {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("folders.yaml");
+    fs::write(&path, content).unwrap();
+    (dir, path)
+}
+
+fn names(path: &Path) -> Vec<String> {
+    load_folders(path)
+        .unwrap()
+        .into_iter()
+        .map(|f| f.name)
+        .collect()
+}
+
+#[test]
+fn test_edit_folders_add() {
+    let (_dir, path) = folders_file(FOLDERS_YAML);
+    let add = FolderEdit::Add {
+        name: "Rust: news".to_string(),
+        filter: "\"my tag\" OR rust".to_string(),
+    };
+    edit_folders_file(&path, &add).unwrap();
+    let content = fs::read_to_string(&path).unwrap();
+    assert!(content.starts_with("# My folders\nfolders:\n  # Top priority\n"));
+    assert!(content.ends_with(
+        "    filter: life\n\n  - name: 'Rust: news'\n    filter: '\"my tag\" OR rust'\n\n# end\n"
+    ));
+    let folders = load_folders(&path).unwrap();
+    assert_eq!(folders[3].name, "Rust: news");
+    assert_eq!(folders[3].filter.to_string(), "\"my tag\" OR rust");
+
+    assert!(edit_folders_file(&path, &add).is_err());
+    let bad = FolderEdit::Add {
+        name: "Bad".to_string(),
+        filter: "tech AND".to_string(),
+    };
+    assert!(edit_folders_file(&path, &bad).is_err());
+}
+
+#[test]
+fn test_edit_folders_creates_file() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("folders.yaml");
+    let add = FolderEdit::Add {
+        name: "Tech".to_string(),
+        filter: "tech".to_string(),
+    };
+    edit_folders_file(&path, &add).unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "folders:\n  - name: Tech\n    filter: tech\n"
+    );
+}
+
+#[test]
+fn test_edit_folders_set_filter_and_rename() {
+    let (_dir, path) = folders_file(FOLDERS_YAML);
+    edit_folders_file(
+        &path,
+        &FolderEdit::SetFilter {
+            name: "Priority".to_string(),
+            filter: "important".to_string(),
+        },
+    )
+    .unwrap();
+    edit_folders_file(
+        &path,
+        &FolderEdit::Rename {
+            from: "AI News".to_string(),
+            to: "AI".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        FOLDERS_YAML
+            .replace("important AND NOT long #", "important #")
+            .replace("name: AI News", "name: AI")
+    );
+
+    let rename = FolderEdit::Rename {
+        from: "AI".to_string(),
+        to: "Leisure".to_string(),
+    };
+    assert!(edit_folders_file(&path, &rename).is_err());
+    let missing = FolderEdit::SetFilter {
+        name: "Nope".to_string(),
+        filter: "x".to_string(),
+    };
+    assert!(edit_folders_file(&path, &missing).is_err());
+}
+
+#[test]
+fn test_edit_folders_move_and_delete() {
+    let (_dir, path) = folders_file(FOLDERS_YAML);
+    edit_folders_file(
+        &path,
+        &FolderEdit::Move {
+            name: "Priority".to_string(),
+            position: 9,
+        },
+    )
+    .unwrap();
+    assert_eq!(names(&path), ["AI News", "Leisure", "Priority"]);
+    // The comment moves with its folder
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "# My folders\nfolders:\n  - name: AI News\n    filter: tech/ai\n\n  - name: Leisure\n    filter: life\n\n  # Top priority\n  - name: Priority\n    filter: important AND NOT long # keep short\n\n# end\n"
+    );
+
+    edit_folders_file(
+        &path,
+        &FolderEdit::Delete {
+            name: "Leisure".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(names(&path), ["AI News", "Priority"]);
+    edit_folders_file(
+        &path,
+        &FolderEdit::Move {
+            name: "Priority".to_string(),
+            position: 0,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "# My folders\nfolders:\n  # Top priority\n  - name: Priority\n    filter: important AND NOT long # keep short\n\n  - name: AI News\n    filter: tech/ai\n\n# end\n"
+    );
+}
+
+#[test]
+fn test_edit_folders_shipped_config() {
+    let (_dir, path) = folders_file(&fs::read_to_string("configs/folders.yaml").unwrap());
+    let before = names(&path);
+    edit_folders_file(
+        &path,
+        &FolderEdit::Move {
+            name: before[0].clone(),
+            position: 2,
+        },
+    )
+    .unwrap();
+    let after = names(&path);
+    assert_eq!(after[2], before[0]);
+    assert_eq!(after.len(), before.len());
+}
+
+#[test]
+fn test_edit_folders_nested() {
+    let (_dir, path) = folders_file(
+        "folders:\n  - name: Tech\n    filter: tech\n  - name: Tech/AI\n    filter: tech/ai\n  - name: Technology\n    filter: technology\n  - name: Tech/AI/Unread\n    filter: unread\n",
+    );
+    edit_folders_file(
+        &path,
+        &FolderEdit::Rename {
+            from: "Tech".to_string(),
+            to: "Computing".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        names(&path),
+        [
+            "Computing",
+            "Computing/AI",
+            "Technology",
+            "Computing/AI/Unread"
+        ]
+    );
+
+    edit_folders_file(
+        &path,
+        &FolderEdit::Delete {
+            name: "Computing/AI".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(names(&path), ["Computing", "Technology"]);
+}
+
+#[test]
+fn test_load_folder_view() {
+    let (_dir, path) = folders_file(
+        "folders:\n  - name: Inbox\n    filter: unread\n    sort: oldest\n    unread: true\n    limit: 50\n    group: source\n  - name: Plain\n    filter: tech\n",
+    );
+    let folders = load_folders(&path).unwrap();
+    assert_eq!(
+        folders[0].view,
+        FolderView {
+            sort: SortOrder::Oldest,
+            unread: true,
+            limit: Some(50),
+            group: Grouping::Source,
+        }
+    );
+    assert_eq!(folders[1].view, FolderView::default());
+
+    let (_dir, path) =
+        folders_file("folders:\n  - name: Bad\n    filter: tech\n    sort: random\n");
+    assert!(load_folders(&path).is_err());
+}
+
+#[test]
+fn test_load_mutes() {
+    let (_dir, path) = folders_file(
+        "folders:\n  - name: Tech\n    filter: tech\n\nmute:\n  - name: Sponsored\n    filter: sponsored OR text~\"sponsored post\"\n    mark_read: true\n  - filter: source:Spammy\n",
+    );
+    let mutes = load_mutes(&path).unwrap();
+    assert_eq!(mutes.len(), 2);
+    assert_eq!(mutes[0].name, "Sponsored");
+    assert!(mutes[0].mark_read);
+    assert_eq!(mutes[1].name, "mute 2");
+    assert!(!mutes[1].mark_read);
+    // The mute list does not change the folders
+    assert_eq!(names(&path), vec!["Tech"]);
+
+    // Folders are optional when only muting
+    let (_dir, path) = folders_file("mute:\n  - filter: \"AND\"\n");
+    let err = load_mutes(&path).unwrap_err().to_string();
+    assert!(err.contains("mute 1"), "{}", err);
+    assert!(load_folders(&path).unwrap().is_empty());
+
+    let (_dir, path) = folders_file("folders:\n  - name: Tech\n    filter: tech\n");
+    assert!(load_mutes(&path).unwrap().is_empty());
+
+    assert!(!load_mutes("configs/folders.yaml").unwrap().is_empty());
+}
+
+#[test]
+fn test_load_retention() {
+    let retention = load_retention("configs/retention.yaml").unwrap();
+    assert!(!retention.policies.is_empty());
+    assert!(retention.keep.is_some());
+
+    let mut file = NamedTempFile::new().unwrap();
+    writeln!(
+        file,
+        "policies:\n  - max_per_source: 100\n  - name: Old news\n    filter: news\n    max_days: 7\n"
+    )
+    .unwrap();
+    let retention = load_retention(file.path()).unwrap();
+    assert_eq!(retention.policies.len(), 2);
+    assert_eq!(retention.policies[0].name, "policy 1");
+    assert_eq!(retention.policies[0].filter, Expr::ALWAYS);
+    assert_eq!(retention.policies[0].max_per_source, Some(100));
+    assert_eq!(retention.policies[1].max_days, Some(7));
+    assert!(retention.keep.is_none());
+    assert!(!retention.prune_after_sync);
+
+    for (yaml, expected) in [
+        (
+            "policies:\n  - name: Nothing\n    filter: news\n",
+            "policy 'Nothing'",
+        ),
+        (
+            "policies:\n  - filter: \"AND\"\n    max_days: 3\n",
+            "policy 1",
+        ),
+        ("keep: \"(\"\npolicies: []\n", "keep"),
+    ] {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "{}", yaml).unwrap();
+        let err = load_retention(file.path()).unwrap_err().to_string();
+        assert!(err.contains(expected), "{}", err);
+    }
+}
+
+#[test]
+fn test_rewrite_folders_file_quotes_new_tags() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("folders.yaml");
+    fs::write(
+        &path,
+        "folders:\n  - name: Plain\n    filter: tech AND news\n  - name: Double\n    filter: \"tech OR x\" # both\n  - name: Single\n    filter: 'NOT tech'\n",
+    )
+    .unwrap();
+
+    let edits = [TagEdit::Rename {
+        from: "tech".to_string(),
+        to: "my tech".to_string(),
+    }];
+    assert_eq!(rewrite_folders_file(&path, &edits).unwrap(), 3);
+
+    let folders = load_folders(&path).unwrap();
+    let filters: Vec<String> = folders.iter().map(|f| f.filter.to_string()).collect();
+    let expected = [
+        "\"my tech\" AND news",
+        "\"my tech\" OR x",
+        "NOT \"my tech\"",
+    ];
+    for (filter, expected) in filters.iter().zip(expected) {
+        assert_eq!(*filter, Expr::parse(expected).unwrap().to_string());
+    }
+    assert!(fs::read_to_string(&path).unwrap().contains("# both"));
+}
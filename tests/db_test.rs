@@ -0,0 +1,485 @@
+use chrono::{Duration, Utc};
+use std::collections::HashSet;
+use tagrss::db::{ArticleQuery, Database, Period, SourceRemoval};
+use tagrss::folder::Expr;
+use tagrss::models::Article;
+use tempfile::TempDir;
+
+fn article(source_id: i64, url: &str, tags: &[&str]) -> Article {
+    Article {
+        id: 0,
+        source_id,
+        source_title: String::new(),
+        url: url.to_string(),
+        title: format!("Article {}", url),
+        content: Some("<p>Body text</p>".to_string()),
+        author: None,
+        published_at: None,
+        word_count: 2,
+        tags: tags.iter().map(|t| t.to_string()).collect(),
+        manual_tags: HashSet::new(),
+        read: false,
+        starred: false,
+    }
+}
+
+#[test]
+fn test_add_articles_skips_stored_urls() {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("test.db")).unwrap();
+    let source = db
+        .add_source("http://a.example/feed", "A", &HashSet::new())
+        .unwrap();
+    db.add_article(&article(source, "a", &["tech"])).unwrap();
+
+    let batch = [
+        article(source, "a", &["tech"]),
+        article(source, "b", &["tech/ai"]),
+        article(source, "c", &[]),
+        article(source, "c", &[]),
+    ];
+    assert_eq!(db.add_articles(&batch).unwrap(), 2);
+    assert_eq!(db.get_articles().unwrap().len(), 3);
+    assert_eq!(db.add_articles(&batch).unwrap(), 0);
+    assert_eq!(db.add_articles(&[]).unwrap(), 0);
+
+    // Triggers still maintain the tag and search indexes
+    assert_eq!(db.articles_with_tag("tech").unwrap().len(), 2);
+    assert_eq!(
+        db.search("body", &Default::default(), ("[", "]"))
+            .unwrap()
+            .len(),
+        3
+    );
+}
+
+#[test]
+fn test_transaction_rolls_back_on_error() {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("test.db")).unwrap();
+    let source = db
+        .add_source("http://a.example/feed", "A", &HashSet::new())
+        .unwrap();
+
+    let result: anyhow::Result<()> = db.transaction(|db| {
+        db.add_articles(&[article(source, "a", &[]), article(source, "b", &[])])?;
+        db.update_source_timestamp(source, Utc::now())?;
+        anyhow::bail!("feed went away")
+    });
+    assert!(result.is_err());
+    assert!(db.get_articles().unwrap().is_empty());
+    assert!(db
+        .get_source(source)
+        .unwrap()
+        .unwrap()
+        .last_updated
+        .is_none());
+
+    // Nested calls join the outer transaction
+    let added = db
+        .transaction(|db| {
+            let added = db.add_articles(&[article(source, "a", &[])])?;
+            db.update_source_timestamp(source, Utc::now())?;
+            Ok(added)
+        })
+        .unwrap();
+    assert_eq!(added, 1);
+    assert!(db
+        .get_source(source)
+        .unwrap()
+        .unwrap()
+        .last_updated
+        .is_some());
+}
+
+#[test]
+fn test_remove_source() {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("test.db")).unwrap();
+    let tags: HashSet<String> = ["tech".to_string()].into();
+    let a = db.add_source("http://a.example/feed", "A", &tags).unwrap();
+    let b = db.add_source("http://b.example/feed", "B", &tags).unwrap();
+    db.add_articles(&[
+        article(a, "a1", &["tech"]),
+        article(a, "a2", &[]),
+        article(b, "b1", &["tech"]),
+    ])
+    .unwrap();
+    let a1 = db.articles_with_tag("tech").unwrap()[0].id;
+    let keep: HashSet<String> = ["keep".to_string()].into();
+    db.set_manual_tags(a1, &keep, false).unwrap();
+    db.mark_read(a1, true).unwrap();
+
+    let expected = SourceRemoval {
+        articles: 2,
+        unread: 1,
+        starred: 0,
+        manual_tags: 1,
+    };
+    assert_eq!(db.source_removal(a, false).unwrap(), expected);
+    assert_eq!(db.get_articles().unwrap().len(), 3);
+    assert_eq!(db.remove_source(a, false).unwrap(), expected);
+
+    assert!(db.get_source(a).unwrap().is_none());
+    let left: Vec<i64> = db
+        .get_articles()
+        .unwrap()
+        .iter()
+        .map(|a| a.source_id)
+        .collect();
+    assert_eq!(left, vec![b]);
+    assert!(db.get_manual_tags().unwrap().is_empty());
+    assert_eq!(db.sources_with_tag("tech").unwrap().len(), 1);
+    assert_eq!(
+        db.search("body", &Default::default(), ("[", "]"))
+            .unwrap()
+            .len(),
+        1
+    );
+
+    // Kept articles stay listed and searchable without a source
+    assert_eq!(
+        db.source_removal(b, true).unwrap(),
+        SourceRemoval::default()
+    );
+    db.remove_source(b, true).unwrap();
+    assert!(db.get_sources().unwrap().is_empty());
+    let kept = db.get_articles().unwrap();
+    assert_eq!(kept.len(), 1);
+    assert_eq!(kept[0].source_id, 0);
+    assert_eq!(kept[0].source_title, "");
+    assert_eq!(db.articles_with_tag("tech").unwrap().len(), 1);
+}
+
+#[test]
+fn test_foreign_keys_enforced() {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("test.db")).unwrap();
+    assert!(db.add_article(&article(42, "orphan", &[])).is_err());
+    assert!(db.get_articles().unwrap().is_empty());
+}
+
+#[test]
+fn test_edit_source() {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("test.db")).unwrap();
+    let a = db
+        .add_source("http://a.example/feed", "A", &HashSet::new())
+        .unwrap();
+    db.add_source("http://b.example/feed", "B", &HashSet::new())
+        .unwrap();
+    assert!(db.get_source(a).unwrap().unwrap().enabled);
+
+    db.set_source_enabled(a, false).unwrap();
+    db.rename_source(a, "Renamed").unwrap();
+    db.set_source_url(a, "http://a.example/atom").unwrap();
+    let source = db.get_source(a).unwrap().unwrap();
+    assert!(!source.enabled);
+    assert_eq!(source.title, "Renamed");
+    assert_eq!(source.url, "http://a.example/atom");
+
+    assert!(db.set_source_url(a, "http://b.example/feed").is_err());
+    db.set_source_enabled(a, true).unwrap();
+    assert!(db.get_source(a).unwrap().unwrap().enabled);
+}
+
+#[test]
+fn test_starred_articles() {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("test.db")).unwrap();
+    let source = db
+        .add_source("http://a.example/feed", "A", &HashSet::new())
+        .unwrap();
+    let a = db.add_article(&article(source, "a", &[])).unwrap();
+    let b = db.add_article(&article(source, "b", &[])).unwrap();
+    assert!(db.starred_articles().unwrap().is_empty());
+
+    assert!(db.set_starred(a, true).unwrap());
+    assert!(db.set_starred(b, true).unwrap());
+    assert!(!db.set_starred(42, true).unwrap());
+    let starred: Vec<i64> = db
+        .starred_articles()
+        .unwrap()
+        .iter()
+        .map(|a| a.id)
+        .collect();
+    assert_eq!(starred, vec![b, a]);
+    assert!(db.get_article(a).unwrap().unwrap().starred);
+
+    db.set_starred(b, false).unwrap();
+    let starred: Vec<i64> = db
+        .starred_articles()
+        .unwrap()
+        .iter()
+        .map(|a| a.id)
+        .collect();
+    assert_eq!(starred, vec![a]);
+    assert_eq!(db.source_removal(source, false).unwrap().starred, 1);
+}
+
+#[test]
+fn test_read_later_queue() {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("test.db")).unwrap();
+    let source = db
+        .add_source("http://a.example/feed", "A", &HashSet::new())
+        .unwrap();
+    let ids: Vec<i64> = ["a", "b", "c"]
+        .iter()
+        .map(|url| db.add_article(&article(source, url, &[])).unwrap())
+        .collect();
+    let queue =
+        |db: &Database| -> Vec<i64> { db.read_later().unwrap().iter().map(|a| a.id).collect() };
+
+    assert_eq!(db.enqueue(ids[0], None).unwrap(), 1);
+    assert_eq!(db.enqueue(ids[1], None).unwrap(), 2);
+    assert_eq!(db.enqueue(ids[2], Some(1)).unwrap(), 1);
+    assert_eq!(queue(&db), vec![ids[2], ids[0], ids[1]]);
+
+    // Queuing again moves; positions are clamped to the queue
+    assert_eq!(db.enqueue(ids[2], Some(10)).unwrap(), 3);
+    assert_eq!(queue(&db), vec![ids[0], ids[1], ids[2]]);
+    assert!(db.enqueue(42, None).is_err());
+
+    assert!(db.dequeue(ids[1]).unwrap());
+    assert!(!db.dequeue(ids[1]).unwrap());
+    assert_eq!(queue(&db), vec![ids[0], ids[2]]);
+
+    // Deleting an article takes it off the queue
+    db.remove_source(source, false).unwrap();
+    assert!(queue(&db).is_empty());
+}
+
+#[test]
+fn test_mark_read_leaves_queue() {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("test.db")).unwrap();
+    let source = db
+        .add_source("http://a.example/feed", "A", &HashSet::new())
+        .unwrap();
+    let ids: Vec<i64> = [("a", "news"), ("b", "tech"), ("c", "news"), ("d", "tech")]
+        .iter()
+        .map(|(url, tag)| db.add_article(&article(source, url, &[tag])).unwrap())
+        .collect();
+    for &id in &ids {
+        db.enqueue(id, None).unwrap();
+    }
+    let queue =
+        |db: &Database| -> Vec<i64> { db.read_later().unwrap().iter().map(|a| a.id).collect() };
+
+    // Marking unread keeps queued articles
+    db.mark_read(ids[0], false).unwrap();
+    assert_eq!(queue(&db), ids);
+
+    db.mark_read(ids[0], true).unwrap();
+    assert_eq!(queue(&db), vec![ids[1], ids[2], ids[3]]);
+
+    let news = Expr::parse("news").unwrap();
+    db.set_read_matching(
+        &ArticleQuery {
+            filter: Some(&news),
+            ..Default::default()
+        },
+        true,
+        "news",
+    )
+    .unwrap();
+    assert_eq!(queue(&db), vec![ids[1], ids[3]]);
+    assert_eq!(db.enqueue(ids[0], None).unwrap(), 3);
+}
+
+#[test]
+fn test_undo_read() {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("test.db")).unwrap();
+    let source = db
+        .add_source("http://a.example/feed", "A", &HashSet::new())
+        .unwrap();
+    let ids: Vec<i64> = ["a", "b", "c"]
+        .iter()
+        .map(|url| db.add_article(&article(source, url, &[])).unwrap())
+        .collect();
+    let read = |db: &Database| -> Vec<bool> {
+        ids.iter()
+            .map(|id| db.get_article(*id).unwrap().unwrap().read)
+            .collect()
+    };
+    assert!(db.undo_read().unwrap().is_none());
+
+    db.mark_read(ids[0], true).unwrap();
+    let first_read = db.read_history(None, None).unwrap()[0].read_at;
+    assert!(first_read.is_some());
+
+    // Only changed articles are recorded
+    assert_eq!(db.set_read(&ids, true, "mark all read").unwrap(), 2);
+    assert_eq!(db.set_read(&ids, true, "again").unwrap(), 0);
+    let last = db.last_read_action().unwrap().unwrap();
+    assert_eq!(last.description, "mark all read");
+    assert_eq!(last.articles, 2);
+    assert_eq!(read(&db), vec![true, true, true]);
+
+    assert_eq!(
+        db.undo_read().unwrap().unwrap().description,
+        "mark all read"
+    );
+    assert_eq!(read(&db), vec![true, false, false]);
+    let history = db.read_history(None, None).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].read_at, first_read);
+
+    db.mark_read(ids[0], false).unwrap();
+    assert!(db.read_history(None, None).unwrap().is_empty());
+    db.undo_read().unwrap();
+    assert_eq!(db.read_history(None, None).unwrap()[0].read_at, first_read);
+    db.undo_read().unwrap();
+    assert_eq!(read(&db), vec![false, false, false]);
+    assert!(db.undo_read().unwrap().is_none());
+}
+
+#[test]
+fn test_read_history_and_stats() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    let db = Database::open(&path).unwrap();
+    let source = db
+        .add_source("http://a.example/feed", "A", &HashSet::new())
+        .unwrap();
+    let old = db
+        .add_article(&article(source, "old", &["tech/ai", "tech/rust"]))
+        .unwrap();
+    let new = db
+        .add_article(&article(source, "new", &["tech", "science"]))
+        .unwrap();
+    db.add_article(&article(source, "unread", &["tech"]))
+        .unwrap();
+    db.set_read(&[old, new], true, "read both").unwrap();
+    rusqlite::Connection::open(&path)
+        .unwrap()
+        .execute(
+            "UPDATE articles SET read_at = '2026-01-05T09:00:00.000Z' WHERE id = ?1",
+            [old],
+        )
+        .unwrap();
+
+    assert!(db.mark_opened(new).unwrap());
+    let opened = db.read_history(None, None).unwrap()[0].opened_at;
+    assert!(opened.is_some());
+    db.mark_opened(new).unwrap();
+    assert!(!db.mark_opened(42).unwrap());
+
+    let history: Vec<i64> = db
+        .read_history(None, None)
+        .unwrap()
+        .iter()
+        .map(|e| e.article.id)
+        .collect();
+    assert_eq!(history, vec![new, old]);
+    let history = db.read_history(None, None).unwrap();
+    assert_eq!(history[0].opened_at, opened);
+    assert!(history[1].opened_at.is_none());
+    assert_eq!(db.read_history(None, Some(1)).unwrap().len(), 1);
+    let recent = db
+        .read_history(Some(Utc::now() - Duration::days(1)), None)
+        .unwrap();
+    assert_eq!(recent.len(), 1);
+
+    let stats = db.reading_stats(Period::Month, None).unwrap();
+    let this_month = Utc::now().format("%Y-%m").to_string();
+    let rows: Vec<(&str, &str, u32)> = stats
+        .iter()
+        .map(|s| (s.period.as_str(), s.tag.as_str(), s.read))
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            (this_month.as_str(), "science", 1),
+            (this_month.as_str(), "tech", 1),
+            ("2026-01", "tech", 1),
+        ]
+    );
+    assert_eq!(
+        db.reading_stats(Period::Week, None)
+            .unwrap()
+            .last()
+            .unwrap()
+            .period,
+        "2026-W01"
+    );
+    assert_eq!(
+        db.reading_stats(Period::Day, Some(Utc::now() - Duration::days(1)))
+            .unwrap()
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn test_set_read_matching() {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("test.db")).unwrap();
+    let a = db
+        .add_source("http://a.example/feed", "A", &HashSet::new())
+        .unwrap();
+    let b = db
+        .add_source("http://b.example/feed", "B", &HashSet::new())
+        .unwrap();
+    let mut old = article(a, "a-old", &["news/world"]);
+    old.published_at = Some(Utc::now() - Duration::days(10));
+    db.add_articles(&[
+        old,
+        article(a, "a-new", &["tech"]),
+        article(b, "b-news", &["news"]),
+        article(b, "b-tech", &["tech/ai"]),
+    ])
+    .unwrap();
+    let unread = |db: &Database| -> Vec<String> {
+        let mut urls: Vec<String> = db
+            .get_articles()
+            .unwrap()
+            .into_iter()
+            .filter(|a| !a.read)
+            .map(|a| a.url)
+            .collect();
+        urls.sort();
+        urls
+    };
+
+    let news = Expr::parse("news").unwrap();
+    let in_a = ArticleQuery {
+        source: Some(a),
+        ..Default::default()
+    };
+    assert_eq!(
+        db.set_read_matching(
+            &ArticleQuery {
+                filter: Some(&news),
+                ..in_a
+            },
+            true,
+            "news from A"
+        )
+        .unwrap(),
+        1
+    );
+    assert_eq!(unread(&db), vec!["a-new", "b-news", "b-tech"]);
+
+    let old = Expr::parse("age>7d").unwrap();
+    let query = ArticleQuery {
+        filter: Some(&old),
+        ..Default::default()
+    };
+    assert_eq!(db.set_read_matching(&query, true, "old").unwrap(), 0);
+
+    let all = ArticleQuery::default();
+    assert_eq!(db.set_read_matching(&all, true, "all").unwrap(), 3);
+    assert!(unread(&db).is_empty());
+    assert_eq!(db.set_read_matching(&in_a, false, "A unread").unwrap(), 2);
+    assert_eq!(unread(&db), vec!["a-new", "a-old"]);
+
+    // Undo goes back one bulk action at a time
+    assert_eq!(db.undo_read().unwrap().unwrap().description, "A unread");
+    assert!(unread(&db).is_empty());
+    assert_eq!(db.undo_read().unwrap().unwrap().articles, 3);
+    assert_eq!(unread(&db), vec!["a-new", "b-news", "b-tech"]);
+    assert_eq!(db.read_history(None, None).unwrap().len(), 1);
+}
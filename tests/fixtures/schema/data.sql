@@ -0,0 +1,14 @@
+-- Sample rows loaded after each historical schema
+INSERT INTO sources (id, url, title, tags, last_updated) VALUES
+    (1, 'http://a.example/feed', 'Peter Turchin', '["science"]', '2026-01-02T08:00:00+00:00'),
+    (2, 'http://b.example/feed', 'Tech Blog', '["tech"]', NULL);
+
+INSERT INTO articles (id, source_id, url, title, content, published_at, word_count, tags, read) VALUES
+    (1, 1, 'http://a.example/1', 'Cliodynamics', '<p>History as science</p>',
+        '2026-01-01T10:00:00+00:00', 1200, '["science","long"]', 1),
+    (2, 2, 'http://b.example/1', 'Rust 2026', '<p>The borrow checker</p>',
+        '2026-01-02T10:00:00+00:00', 300, '["tech/programming/rust"]', 0),
+    (3, 2, 'http://b.example/2', 'Untitled draft', NULL, NULL, 0, '[]', 0);
+
+INSERT INTO rules (id, rule_json) VALUES
+    (1, '{"type":"Contains","pattern":"rust","case_sensitive":false,"tag":"tech/programming/rust"}');
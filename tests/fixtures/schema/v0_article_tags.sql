@@ -0,0 +1,66 @@
+-- Classifier schema plus article_tags and its triggers, no user_version
+CREATE TABLE sources (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    url TEXT NOT NULL UNIQUE,
+    title TEXT NOT NULL,
+    tags TEXT NOT NULL DEFAULT '[]',
+    last_updated TEXT
+);
+
+CREATE TABLE articles (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    source_id INTEGER NOT NULL REFERENCES sources(id),
+    url TEXT NOT NULL UNIQUE,
+    title TEXT NOT NULL,
+    content TEXT,
+    published_at TEXT,
+    word_count INTEGER NOT NULL DEFAULT 0,
+    tags TEXT NOT NULL DEFAULT '[]',
+    read INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE rules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    rule_json TEXT NOT NULL
+);
+
+CREATE INDEX idx_articles_source ON articles(source_id);
+CREATE INDEX idx_articles_read ON articles(read);
+
+CREATE TABLE manual_tags (
+    article_id INTEGER NOT NULL REFERENCES articles(id),
+    tag TEXT NOT NULL,
+    removed INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (article_id, tag)
+);
+
+CREATE TABLE classifier (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    model_json TEXT NOT NULL,
+    trained_at TEXT NOT NULL
+);
+
+CREATE TABLE article_tags (
+    article_id INTEGER NOT NULL REFERENCES articles(id),
+    tag TEXT NOT NULL,
+    PRIMARY KEY (article_id, tag)
+);
+
+CREATE TRIGGER article_tags_insert AFTER INSERT ON articles BEGIN
+    INSERT OR IGNORE INTO article_tags (article_id, tag)
+    SELECT new.id, value FROM json_each(new.tags);
+END;
+
+CREATE TRIGGER article_tags_update AFTER UPDATE OF tags ON articles BEGIN
+    DELETE FROM article_tags WHERE article_id = old.id;
+    INSERT OR IGNORE INTO article_tags (article_id, tag)
+    SELECT new.id, value FROM json_each(new.tags);
+END;
+
+CREATE TRIGGER article_tags_delete AFTER DELETE ON articles BEGIN
+    DELETE FROM article_tags WHERE article_id = old.id;
+END;
+
+CREATE INDEX idx_articles_published ON articles(published_at);
+CREATE INDEX idx_article_tags_tag ON article_tags(tag);
@@ -0,0 +1,29 @@
+-- Schema of the first release: sources, articles and rules, no user_version
+CREATE TABLE sources (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    url TEXT NOT NULL UNIQUE,
+    title TEXT NOT NULL,
+    tags TEXT NOT NULL DEFAULT '[]',
+    last_updated TEXT
+);
+
+CREATE TABLE articles (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    source_id INTEGER NOT NULL REFERENCES sources(id),
+    url TEXT NOT NULL UNIQUE,
+    title TEXT NOT NULL,
+    content TEXT,
+    published_at TEXT,
+    word_count INTEGER NOT NULL DEFAULT 0,
+    tags TEXT NOT NULL DEFAULT '[]',
+    read INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE rules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    rule_json TEXT NOT NULL
+);
+
+CREATE INDEX idx_articles_source ON articles(source_id);
+CREATE INDEX idx_articles_read ON articles(read);
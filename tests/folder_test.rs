@@ -1,4 +1,15 @@
-use tagrss::folder::Expr;
+use chrono::{Duration, Utc};
+use std::collections::HashSet;
+use tagrss::config::{load_folders, rewrite_folders_file};
+use tagrss::db::{ArticleQuery, Database};
+use tagrss::folder::{
+    build_tree, group_articles, mute_filter, parse_age, quote_tag, resolve, rewrite_filter_tags,
+    AgeUnit, Cmp, Expr, Folder, FolderCount, FolderView, Grouping, Mute, SortOrder,
+};
+use tagrss::models::Article;
+use tagrss::tags::TagEdit;
+use tagrss::text::detect_lang;
+use tempfile::TempDir;
 
 #[test]
 fn test_parse_simple() {
@@ -63,3 +74,1070 @@ fn test_parse_hierarchical_tag() {
     let expr = Expr::parse("tech/ai/llm").unwrap();
     assert!(matches!(expr, Expr::Tag { name } if name == "tech/ai/llm"));
 }
+
+#[test]
+fn test_parse_rejects_trailing_input() {
+    let err = Expr::parse("tech important").unwrap_err();
+    assert_eq!((err.span.start, err.span.end), (5, 14));
+    assert!(err.expected.contains(&"end of input".to_string()));
+}
+
+#[test]
+fn test_parse_unmatched_paren() {
+    let err = Expr::parse("tech)").unwrap_err();
+    assert_eq!(err.span.start, 4);
+    assert!(err.message.contains("unmatched"));
+
+    let err = Expr::parse("(tech AND news").unwrap_err();
+    assert_eq!(err.span.start, 14);
+    assert!(err.expected.contains(&")".to_string()));
+}
+
+#[test]
+fn test_parse_error_missing_operand() {
+    let err = Expr::parse("tech AND").unwrap_err();
+    assert_eq!(err.span.start, 8);
+    assert_eq!(err.expected, vec!["tag", "predicate", "NOT", "("]);
+}
+
+#[test]
+fn test_parse_error_render() {
+    let err = Expr::parse("tech important").unwrap_err();
+    assert_eq!(
+        err.render("tech important"),
+        "unexpected tag 'important' after expression, expected AND, OR or end of input\n  tech important\n       ^^^^^^^^^"
+    );
+}
+
+#[test]
+fn test_parse_double_not() {
+    let expr = Expr::parse("NOT NOT life").unwrap();
+    assert!(matches!(expr, Expr::Not { expr } if matches!(*expr, Expr::Not { .. })));
+}
+
+// ==================== Field predicate tests ====================
+
+fn make_article() -> Article {
+    Article {
+        id: 1,
+        source_id: 1,
+        source_title: "Peter Turchin".to_string(),
+        url: "http://example.com".to_string(),
+        title: "Rust in Production".to_string(),
+        content: Some("<p>The borrow checker and the type system</p>".to_string()),
+        author: None,
+        published_at: Some(Utc::now() - Duration::days(2)),
+        word_count: 2500,
+        tags: ["tech/ai".to_string()].into(),
+        manual_tags: HashSet::new(),
+        read: false,
+        starred: false,
+    }
+}
+
+fn check(filter: &str) -> bool {
+    Expr::parse(filter).unwrap().matches(&make_article())
+}
+
+#[test]
+fn test_parse_predicates() {
+    assert_eq!(Expr::parse("unread").unwrap(), Expr::Read { read: false });
+    assert_eq!(
+        Expr::parse("starred").unwrap(),
+        Expr::Starred { starred: true }
+    );
+    assert_eq!(
+        Expr::parse("source:\"Peter Turchin\"").unwrap(),
+        Expr::Source {
+            name: "Peter Turchin".to_string()
+        }
+    );
+    assert_eq!(
+        Expr::parse("words>=2000").unwrap(),
+        Expr::Words {
+            cmp: Cmp::Ge,
+            count: 2000
+        }
+    );
+    assert_eq!(
+        Expr::parse("age<3d").unwrap(),
+        Expr::Age {
+            cmp: Cmp::Lt,
+            amount: 3,
+            unit: AgeUnit::Days
+        }
+    );
+    assert!(matches!(
+        Expr::parse("published>2026-01-01").unwrap(),
+        Expr::Published { cmp: Cmp::Gt, .. }
+    ));
+}
+
+#[test]
+fn test_predicates_match() {
+    assert!(check("unread"));
+    assert!(!check("read"));
+    assert!(check("unstarred"));
+    assert!(!check("starred"));
+    assert!(check("source:\"peter turchin\""));
+    assert!(!check("source:Razib"));
+    assert!(check("words>2000"));
+    assert!(!check("words<2000"));
+    assert!(check("age<3d"));
+    assert!(!check("age<24h"));
+    assert!(!check("age>1w"));
+    assert!(check("published>2000-01-01"));
+    assert!(check("title~rust"));
+    assert!(check("title~\"in production\""));
+    assert!(check("lang:en"));
+    assert!(!check("lang:zh"));
+}
+
+#[test]
+fn test_predicates_combine_with_tags() {
+    assert!(check("tech/ai AND unread AND words>2000 AND age<7d"));
+    assert!(check("(source:\"Peter Turchin\" OR news) AND NOT read"));
+}
+
+#[test]
+fn test_predicate_errors() {
+    let err = Expr::parse("tech AND words>lots").unwrap_err();
+    assert_eq!((err.span.start, err.span.end), (9, 19));
+    assert!(Expr::parse("age<3").is_err());
+    assert!(Expr::parse("published>yesterday").is_err());
+    assert!(Expr::parse("words~100").is_err());
+    assert!(Expr::parse("source:\"Peter").is_err());
+}
+
+#[test]
+fn test_unknown_field_is_a_tag() {
+    let expr = Expr::parse("lang-learning").unwrap();
+    assert!(matches!(expr, Expr::Tag { name } if name == "lang-learning"));
+    let expr = Expr::parse("ns:tag").unwrap();
+    assert!(matches!(expr, Expr::Tag { name } if name == "ns:tag"));
+}
+
+#[test]
+fn test_detect_lang() {
+    assert_eq!(detect_lang("The state of the art", None), "en");
+    assert_eq!(detect_lang("今天看点啥", Some("<p>知识管理之术</p>")), "zh");
+    assert_eq!(detect_lang("ひらがなとカタカナ", None), "ja");
+    assert_eq!(detect_lang("Die Katze und der Hund", None), "de");
+    assert_eq!(detect_lang("12345", None), "und");
+}
+
+// ==================== Quoting, globs and symbolic operator tests ====================
+
+fn tagged(tags: &[&str]) -> Article {
+    Article {
+        tags: tags.iter().map(|s| s.to_string()).collect(),
+        ..make_article()
+    }
+}
+
+#[test]
+fn test_parse_quoted_tag() {
+    let expr = Expr::parse("\"reading list (2026)\" AND \"unread\"").unwrap();
+    assert_eq!(
+        expr,
+        Expr::And {
+            exprs: vec![
+                Expr::Tag {
+                    name: "reading list (2026)".to_string()
+                },
+                Expr::Tag {
+                    name: "unread".to_string()
+                },
+            ]
+        }
+    );
+}
+
+#[test]
+fn test_exact_match() {
+    let expr = Expr::parse("=tech").unwrap();
+    assert_eq!(
+        expr,
+        Expr::Exact {
+            name: "tech".to_string()
+        }
+    );
+    assert!(expr.matches(&tagged(&["tech"])));
+    assert!(!expr.matches(&tagged(&["tech/ai"])));
+    assert!(Expr::parse("tech").unwrap().matches(&tagged(&["tech/ai"])));
+}
+
+#[test]
+fn test_glob_match() {
+    let expr = Expr::parse("*/rust").unwrap();
+    assert!(expr.matches(&tagged(&["lang/rust"])));
+    assert!(!expr.matches(&tagged(&["tech/programming/rust"])));
+
+    let expr = Expr::parse("tech/*/llm").unwrap();
+    assert!(expr.matches(&tagged(&["tech/ai/llm"])));
+    assert!(expr.matches(&tagged(&["tech/ai/llm/gpt"])));
+    assert!(!expr.matches(&tagged(&["tech/llm"])));
+
+    let expr = Expr::parse("=tech/*").unwrap();
+    assert!(expr.matches(&tagged(&["tech/ai"])));
+    assert!(!expr.matches(&tagged(&["tech/ai/llm"])));
+
+    assert!(Expr::parse("**/rust")
+        .unwrap()
+        .matches(&tagged(&["tech/programming/rust"])));
+}
+
+#[test]
+fn test_symbolic_operators() {
+    let words = Expr::parse("important AND NOT (life OR long)").unwrap();
+    assert_eq!(Expr::parse("important & !(life | long)").unwrap(), words);
+    assert_eq!(Expr::parse("important && -(life || long)").unwrap(), words);
+    assert_eq!(
+        Expr::parse("tech -news").unwrap_err().message,
+        "unexpected NOT after expression"
+    );
+    assert_eq!(
+        Expr::parse("tech & -news").unwrap(),
+        Expr::parse("tech AND NOT news").unwrap()
+    );
+    assert!(Expr::parse("machine-learning").is_ok());
+    assert!(Expr::parse("tech & - news").is_err());
+}
+
+#[test]
+fn test_quote_tag() {
+    assert_eq!(quote_tag("tech/ai"), "tech/ai");
+    assert_eq!(quote_tag("my tag"), "\"my tag\"");
+    assert_eq!(quote_tag("unread"), "\"unread\"");
+    assert_eq!(quote_tag("starred"), "\"starred\"");
+    assert_eq!(quote_tag("OR"), "\"OR\"");
+    assert_eq!(quote_tag("a*b"), "\"a*b\"");
+    assert_eq!(quote_tag("say \"hi\""), "\"say \\\"hi\\\"\"");
+    for name in ["my tag", "unread", "say \"hi\"", "=x", "-x"] {
+        assert_eq!(
+            Expr::parse(&quote_tag(name)).unwrap(),
+            Expr::Tag {
+                name: name.to_string()
+            }
+        );
+    }
+}
+
+#[test]
+fn test_rewrite_filter_keeps_exact_and_quotes() {
+    let out = rewrite_filter_tags("=tech & !\"tech\" | tech/*", |t| {
+        if t == "tech" {
+            "my tech".to_string()
+        } else {
+            t.to_string()
+        }
+    });
+    assert_eq!(out, "=\"my tech\" & !\"my tech\" | \"my tech/\"*");
+}
+
+#[test]
+fn test_parse_age() {
+    assert_eq!(parse_age("12h"), Some((12, AgeUnit::Hours)));
+    assert_eq!(parse_age("7d"), Some((7, AgeUnit::Days)));
+    assert_eq!(parse_age("2w"), Some((2, AgeUnit::Weeks)));
+    for bad in ["", "d", "7", "7y", "-1d", "7d | tag:x", "7d OR news"] {
+        assert_eq!(parse_age(bad), None, "{}", bad);
+    }
+}
+
+#[test]
+fn test_rename_round_trip() {
+    let tag = |name: &str| Expr::Tag {
+        name: name.to_string(),
+    };
+    for new in ["my tech", "say \"hi\"", "unread", "back\\slash"] {
+        let edit = TagEdit::Rename {
+            from: "tech".to_string(),
+            to: new.to_string(),
+        };
+        let out = rewrite_filter_tags("tech/ai AND NOT news", |t| edit.rewrite(t).unwrap());
+        assert_eq!(
+            Expr::parse(&out).unwrap(),
+            Expr::And {
+                exprs: vec![
+                    tag(&format!("{}/ai", new)),
+                    Expr::Not {
+                        expr: Box::new(tag("news"))
+                    },
+                ]
+            }
+        );
+
+        // Through a folders file, which has to escape the quoted tags
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("folders.yaml");
+        std::fs::write(
+            &path,
+            "folders:\n  - name: A\n    filter: tech/ai AND NOT news\n  - name: B\n    filter: \"tech/ai AND NOT news\"\n",
+        )
+        .unwrap();
+        assert_eq!(rewrite_folders_file(&path, &[edit]).unwrap(), 2);
+        for folder in load_folders(&path).unwrap() {
+            assert_eq!(folder.filter, Expr::parse(&out).unwrap());
+        }
+    }
+}
+
+// ==================== SQL compilation tests ====================
+
+/// A database with articles covering every predicate, including edge cases
+/// like missing publication dates, tag prefixes and non-ASCII titles
+fn sample_db(dir: &TempDir) -> Database {
+    let db = Database::open(dir.path().join("test.db")).unwrap();
+    let tags = |t: &[&str]| t.iter().map(|s| s.to_string()).collect::<HashSet<_>>();
+    let turchin = db
+        .add_source("http://a.example/feed", "Peter Turchin", &tags(&[]))
+        .unwrap();
+    let other = db
+        .add_source("http://b.example/feed", "Ünïcode Blog", &tags(&[]))
+        .unwrap();
+
+    let now = Utc::now();
+    let samples = [
+        (
+            turchin,
+            "Rust and the future",
+            &["tech/programming/rust", "long"][..],
+            3500,
+            Some(1),
+            false,
+        ),
+        (
+            turchin,
+            "Cliodynamics",
+            &["science", "tech"],
+            1200,
+            Some(10),
+            true,
+        ),
+        (
+            other,
+            "ÉTÉ in Paris: le vin et la cuisine",
+            &["life/travel"],
+            800,
+            Some(40),
+            false,
+        ),
+        (
+            other,
+            "机器学习的未来发展与应用",
+            &["tech/ai/llm"],
+            2500,
+            None,
+            false,
+        ),
+        (
+            other,
+            "Technology roundup",
+            &["technology", "tech/"],
+            300,
+            Some(3),
+            true,
+        ),
+        (
+            turchin,
+            "my tag (x) and more",
+            &["my tag (x)", "lang/rust"],
+            0,
+            Some(400),
+            false,
+        ),
+        (other, "Untagged", &[], 100, Some(0), false),
+    ];
+    for (i, (source_id, title, t, words, days, read)) in samples.into_iter().enumerate() {
+        db.add_article(&Article {
+            id: 0,
+            source_id,
+            source_title: String::new(),
+            url: format!("http://example.com/{}", i),
+            title: title.to_string(),
+            content: Some(format!("<p>{}</p>", title)),
+            author: None,
+            published_at: days.map(|d| now - Duration::days(d) - Duration::hours(1)),
+            word_count: words,
+            tags: tags(t),
+            manual_tags: HashSet::new(),
+            read,
+            starred: i % 3 == 1,
+        })
+        .unwrap();
+    }
+    db
+}
+
+fn sql_ids(db: &Database, expr: &Expr) -> Vec<i64> {
+    let mut ids: Vec<i64> = db
+        .query_articles(&ArticleQuery {
+            filter: Some(expr),
+            ..Default::default()
+        })
+        .unwrap()
+        .iter()
+        .map(|a| a.id)
+        .collect();
+    ids.sort();
+    ids
+}
+
+fn rust_ids(db: &Database, expr: &Expr) -> Vec<i64> {
+    let mut ids: Vec<i64> = db
+        .get_articles()
+        .unwrap()
+        .iter()
+        .filter(|a| expr.matches(a))
+        .map(|a| a.id)
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn test_sql_matches_in_memory() {
+    let dir = TempDir::new().unwrap();
+    let db = sample_db(&dir);
+    let filters = [
+        "tech",
+        "tech/ai",
+        "=tech",
+        "NOT tech",
+        "technology",
+        "\"my tag (x)\"",
+        "tech/*",
+        "=tech/*",
+        "*/rust",
+        "**/rust",
+        "te*",
+        "unread",
+        "read AND tech",
+        "starred",
+        "unstarred AND tech",
+        "source:turchin",
+        "source:\"ünï\"",
+        "words>1000",
+        "words<=800",
+        "words=0",
+        "age<7d",
+        "age>=1w",
+        "NOT age<7d",
+        "age>48h",
+        "published>2020-01-01",
+        "NOT published<2100-01-01",
+        "title~rust",
+        "title~été",
+        "text~rust",
+        "text~\"<p>\"",
+        "NOT text~cuisine",
+        "lang:zh",
+        "lang:FR",
+        "NOT lang:en",
+        "(tech OR life) AND NOT long",
+        "tech | science & !read",
+        "NOT (tech AND unread) OR words>3000",
+        "NOT NOT =tech/",
+    ];
+    for filter in filters {
+        let expr = Expr::parse(filter).unwrap();
+        assert_eq!(
+            sql_ids(&db, &expr),
+            rust_ids(&db, &expr),
+            "filter: {}",
+            filter
+        );
+    }
+}
+
+#[test]
+fn test_sql_empty_and_or() {
+    let dir = TempDir::new().unwrap();
+    let db = sample_db(&dir);
+    let all = Expr::And { exprs: Vec::new() };
+    let none = Expr::Or { exprs: Vec::new() };
+    assert_eq!(sql_ids(&db, &all), rust_ids(&db, &all));
+    assert_eq!(sql_ids(&db, &none), rust_ids(&db, &none));
+    assert_eq!(sql_ids(&db, &all).len(), 7);
+}
+
+#[test]
+fn test_query_articles_unread_and_limit() {
+    let dir = TempDir::new().unwrap();
+    let db = sample_db(&dir);
+    let expr = Expr::parse("tech").unwrap();
+    let articles = db
+        .query_articles(&ArticleQuery {
+            filter: Some(&expr),
+            unread: true,
+            limit: Some(1),
+            ..Default::default()
+        })
+        .unwrap();
+    // Newest unread tech article; #4 has no date and sorts last
+    assert_eq!(articles.len(), 1);
+    assert_eq!(articles[0].id, 1);
+
+    let all = db.query_articles(&ArticleQuery::default()).unwrap();
+    let ids: Vec<i64> = all.iter().map(|a| a.id).collect();
+    let expected: Vec<i64> = db.get_articles().unwrap().iter().map(|a| a.id).collect();
+    assert_eq!(ids, expected);
+}
+
+#[test]
+fn test_sql_follows_tag_updates() {
+    let dir = TempDir::new().unwrap();
+    let db = sample_db(&dir);
+    let expr = Expr::parse("ai").unwrap();
+    assert!(sql_ids(&db, &expr).is_empty());
+
+    db.rewrite_tags(&[TagEdit::Rename {
+        from: "tech/ai".to_string(),
+        to: "ai".to_string(),
+    }])
+    .unwrap();
+    assert_eq!(sql_ids(&db, &expr), vec![4]);
+
+    let mine: HashSet<String> = ["ai/mine".to_string()].into();
+    db.set_manual_tags(1, &mine, false).unwrap();
+    assert_eq!(sql_ids(&db, &expr), vec![1, 4]);
+    db.update_article_tags(4, &HashSet::new()).unwrap();
+    assert_eq!(sql_ids(&db, &expr), vec![1]);
+}
+
+// ==================== Display and simplification tests ====================
+
+#[test]
+fn test_display_round_trip() {
+    let filters = [
+        "tech",
+        "=tech/ai",
+        "\"my tag (x)\" OR \"unread\"",
+        "tech/* AND =*/rust",
+        "important AND NOT long",
+        "life OR (NOT important AND NOT tech)",
+        "(a OR b) AND (c OR d)",
+        "(a OR b) OR c",
+        "a OR (b OR c)",
+        "(a AND b) AND c",
+        "NOT (a OR b)",
+        "NOT NOT a",
+        "NOT (a AND b) OR c",
+        "unread AND source:\"Peter Turchin\" AND words>=2000",
+        "age<3d OR published<=2026-01-01 OR title~\"say \\\"hi\\\"\" OR lang:zh",
+        "text~\"sponsored post\" AND NOT text~ad",
+        "TRUE AND NOT false",
+        "\"true\" OR \"my tag\"/* OR =\"a b\"*\"(c)\"",
+    ];
+    for filter in filters {
+        let expr = Expr::parse(filter).unwrap();
+        let text = expr.to_string();
+        assert_eq!(Expr::parse(&text).unwrap(), expr, "{} -> {}", filter, text);
+    }
+    assert_eq!(Expr::parse("TRUE").unwrap(), Expr::ALWAYS);
+    assert_eq!(Expr::NEVER.to_string(), "FALSE");
+    assert_eq!(
+        Expr::parse("\"my tag\"/*").unwrap(),
+        Expr::Glob {
+            pattern: "my tag/*".to_string(),
+            exact: false
+        }
+    );
+}
+
+/// Deterministic pseudo-random expressions with awkward tag names and values
+struct ExprGen(u64);
+
+impl ExprGen {
+    fn next(&mut self, n: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) % n as u64) as usize
+    }
+
+    fn pick(&mut self, items: &[&str]) -> String {
+        items[self.next(items.len())].to_string()
+    }
+
+    fn expr(&mut self, depth: u32) -> Expr {
+        const NAMES: &[&str] = &[
+            "tech",
+            "tech/ai",
+            "my tag",
+            "unread",
+            "and",
+            "true",
+            "-x",
+            "=y",
+            "title:z",
+            "say \"hi\"",
+            "back\\slash",
+            "(a)",
+            "a|b",
+        ];
+        const GLOBS: &[&str] = &[
+            "tech/*",
+            "*/rust",
+            "**/ai",
+            "my tag/*",
+            "*\"q\"*",
+            "=a*",
+            "-b*",
+            "source:*",
+            "(x)*",
+            "a b/*/c d",
+        ];
+        let cmp = [Cmp::Lt, Cmp::Le, Cmp::Gt, Cmp::Ge, Cmp::Eq][self.next(5)];
+        let max = if depth == 0 { 13 } else { 16 };
+        match self.next(max) {
+            0 => Expr::Tag {
+                name: self.pick(NAMES),
+            },
+            1 => Expr::Exact {
+                name: self.pick(NAMES),
+            },
+            2 => Expr::Glob {
+                pattern: self.pick(GLOBS),
+                exact: self.next(2) == 0,
+            },
+            3 => Expr::Read {
+                read: self.next(2) == 0,
+            },
+            4 => Expr::Starred {
+                starred: self.next(2) == 0,
+            },
+            5 => Expr::Source {
+                name: self.pick(NAMES),
+            },
+            6 => Expr::Words {
+                cmp,
+                count: self.next(5000) as u32,
+            },
+            7 => Expr::Age {
+                cmp,
+                amount: self.next(100) as u32,
+                unit: [AgeUnit::Hours, AgeUnit::Days, AgeUnit::Weeks][self.next(3)],
+            },
+            8 => Expr::Published {
+                cmp,
+                date: chrono::NaiveDate::from_ymd_opt(2026, 1, 1 + self.next(28) as u32).unwrap(),
+            },
+            9 => Expr::Title {
+                text: self.pick(NAMES),
+            },
+            10 => Expr::Text {
+                text: self.pick(NAMES),
+            },
+            11 => Expr::Lang {
+                code: self.pick(&["en", "zh", "und"]),
+            },
+            12 => [Expr::ALWAYS, Expr::NEVER][self.next(2)].clone(),
+            13 => Expr::Not {
+                expr: Box::new(self.expr(depth - 1)),
+            },
+            n => {
+                let exprs = (0..1 + self.next(3))
+                    .map(|_| self.expr(depth - 1))
+                    .collect();
+                if n == 14 {
+                    Expr::And { exprs }
+                } else {
+                    Expr::Or { exprs }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_display_round_trip_generated() {
+    let mut gen = ExprGen(7);
+    for _ in 0..2000 {
+        let expr = gen.expr(4).simplify();
+        let text = expr.to_string();
+        let parsed = Expr::parse(&text).unwrap_or_else(|e| panic!("{}: {}", text, e));
+        assert_eq!(parsed, expr, "{}", text);
+    }
+}
+
+#[test]
+fn test_display_minimal_parens() {
+    let show = |s: &str| Expr::parse(s).unwrap().to_string();
+    assert_eq!(show("((tech)) & !(life)"), "tech AND NOT life");
+    assert_eq!(show("(a AND b) OR c"), "a AND b OR c");
+    assert_eq!(show("a AND (b OR c)"), "a AND (b OR c)");
+    assert_eq!(show("!(a | b)"), "NOT (a OR b)");
+    assert_eq!(show("-tech && words>10"), "NOT tech AND words>10");
+    assert_eq!(
+        show("source:turchin | title~\"a b\""),
+        "source:turchin OR title~\"a b\""
+    );
+}
+
+#[test]
+fn test_simplify() {
+    let simplify = |s: &str| Expr::parse(s).unwrap().simplify().to_string();
+    assert_eq!(simplify("(a AND b) AND (c AND a)"), "a AND b AND c");
+    assert_eq!(simplify("(a OR b) OR c"), "a OR b OR c");
+    assert_eq!(simplify("NOT NOT a"), "a");
+    assert_eq!(simplify("NOT read"), "unread");
+    assert_eq!(simplify("NOT starred"), "unstarred");
+    assert_eq!(simplify("a AND (a OR b)"), "a");
+    assert_eq!(simplify("a OR (a AND b)"), "a");
+    assert_eq!(simplify("a OR NOT a"), "TRUE");
+    assert_eq!(simplify("x AND (a OR NOT a)"), "x");
+    assert_eq!(simplify("tech OR NOT tech/ai"), "TRUE");
+    assert_eq!(
+        simplify("(a OR b) AND NOT (c OR d)"),
+        "(a OR b) AND NOT (c OR d)"
+    );
+}
+
+#[test]
+fn test_never_matches() {
+    for filter in [
+        "a AND NOT a",
+        "tech/ai AND NOT tech",
+        "=tech/ai/llm AND -tech",
+        "read AND unread",
+        "starred AND unstarred",
+        "x AND (a AND NOT a)",
+        "NOT (a OR NOT a)",
+        "(a AND NOT a) OR (b AND NOT b)",
+    ] {
+        assert!(Expr::parse(filter).unwrap().is_never(), "{}", filter);
+    }
+    for filter in [
+        "tech AND NOT tech/ai",
+        "a AND NOT b",
+        "=tech AND NOT tech/ai",
+    ] {
+        assert!(!Expr::parse(filter).unwrap().is_never(), "{}", filter);
+    }
+}
+
+#[test]
+fn test_simplify_preserves_matches() {
+    let dir = TempDir::new().unwrap();
+    let db = sample_db(&dir);
+    for filter in [
+        "tech AND (tech OR life)",
+        "NOT NOT tech/ai",
+        "(tech OR long) OR (science AND unread)",
+        "tech/programming AND NOT tech",
+        "unread OR read",
+    ] {
+        let expr = Expr::parse(filter).unwrap();
+        assert_eq!(
+            rust_ids(&db, &expr.simplify()),
+            rust_ids(&db, &expr),
+            "{}",
+            filter
+        );
+    }
+}
+
+// ==================== Nested folder tests ====================
+
+fn nested_folders() -> Vec<Folder> {
+    [
+        ("Tech", "tech"),
+        ("Tech/AI", "tech/ai OR ml"),
+        ("Tech/AI/Unread", "unread"),
+        ("Life/Travel", "life/travel"),
+        ("Deep Reads", "long"),
+    ]
+    .into_iter()
+    .map(|(name, filter)| Folder {
+        name: name.to_string(),
+        filter: Expr::parse(filter).unwrap(),
+        view: FolderView::default(),
+    })
+    .collect()
+}
+
+#[test]
+fn test_resolve_nested_folder() {
+    let folders = nested_folders();
+    let resolved = resolve(&folders, "Tech/AI/Unread").unwrap();
+    assert_eq!(resolved.name, "Tech/AI/Unread");
+    assert_eq!(
+        resolved.filter.to_string(),
+        "tech AND (tech/ai OR ml) AND unread"
+    );
+    assert_eq!(
+        resolve(&folders, "Tech").unwrap().filter.to_string(),
+        "tech"
+    );
+    assert_eq!(folders[1].parent(), Some("Tech"));
+    assert_eq!(folders[0].parent(), None);
+
+    // "Life" is only implied by its children, and matches what they match
+    assert_eq!(
+        resolve(&folders, "Life").unwrap().filter.to_string(),
+        "life/travel"
+    );
+    let mut folders = folders;
+    folders.push(Folder {
+        name: "Life/Home/Garden".to_string(),
+        filter: Expr::parse("garden").unwrap(),
+        view: FolderView::default(),
+    });
+    assert_eq!(
+        resolve(&folders, "Life").unwrap().filter.to_string(),
+        "life/travel OR garden"
+    );
+    assert_eq!(
+        resolve(&folders, "Life/Home").unwrap().filter.to_string(),
+        "garden"
+    );
+    assert!(resolve(&folders, "Tec").is_none());
+    assert!(resolve(&folders, "Tech/Web").is_none());
+}
+
+#[test]
+fn test_build_folder_tree() {
+    let tree = build_tree(&nested_folders());
+    let names: Vec<&str> = tree.iter().map(|n| n.name.as_str()).collect();
+    assert_eq!(names, ["Tech", "Life", "Deep Reads"]);
+    let ai = &tree[0].children[0];
+    assert_eq!(ai.path, "Tech/AI");
+    assert_eq!(ai.children[0].path, "Tech/AI/Unread");
+    assert_eq!(tree[1].filter, None);
+    assert_eq!(tree[1].children[0].name, "Travel");
+}
+
+#[test]
+fn test_nested_folder_counts() {
+    let dir = TempDir::new().unwrap();
+    let db = sample_db(&dir);
+    let folders = nested_folders();
+    let count = |path: &str| {
+        let folder = resolve(&folders, path).unwrap();
+        db.count_articles(&ArticleQuery {
+            filter: Some(&folder.filter),
+            ..Default::default()
+        })
+        .unwrap()
+    };
+    let count = |path: &str| {
+        let counts = count(path);
+        (counts.total, counts.unread)
+    };
+    assert_eq!(count("Tech"), (4, 2));
+    assert_eq!(count("Tech/AI"), (1, 1));
+    assert_eq!(count("Tech/AI/Unread"), (1, 1));
+    // Implied by "Life/Travel" alone, not every article
+    assert_eq!(count("Life"), count("Life/Travel"));
+    assert_eq!(count("Life"), (1, 1));
+}
+
+#[test]
+fn test_contradicting_nested_folder_is_never() {
+    let dir = TempDir::new().unwrap();
+    let db = sample_db(&dir);
+    let mut folders = nested_folders();
+    folders.push(Folder {
+        name: "Tech/Archive".to_string(),
+        filter: Expr::parse("NOT tech").unwrap(),
+        view: FolderView::default(),
+    });
+    // On its own the child matches, but never under its parent
+    assert!(!folders[5].filter.is_never());
+    let resolved: Vec<Folder> = folders
+        .iter()
+        .filter_map(|f| resolve(&folders, &f.name))
+        .collect();
+    assert!(resolved[5].filter.is_never());
+    assert!(resolved.iter().take(5).all(|f| !f.filter.is_never()));
+
+    let filters: Vec<Expr> = resolved.iter().map(|f| f.filter.clone()).collect();
+    let counts = db.count_filters(&filters, None).unwrap();
+    assert_eq!(counts[5].total, 0);
+    assert_eq!(counts[0].total, 4);
+}
+
+#[test]
+fn test_folder_counts_match_queries() {
+    let dir = TempDir::new().unwrap();
+    let db = sample_db(&dir);
+    let folders = nested_folders();
+    let tree = db.folder_counts(&folders, None).unwrap();
+
+    let names: Vec<&str> = tree.iter().map(|n| n.name.as_str()).collect();
+    assert_eq!(names, vec!["Tech", "Life", "Deep Reads"]);
+    let life = &tree[1];
+    assert_eq!(life.filter, None);
+    assert_eq!(life.children[0].filter.as_deref(), Some("life/travel"));
+
+    fn check(db: &Database, folders: &[Folder], nodes: &[FolderCount]) {
+        for node in nodes {
+            let folder = resolve(folders, &node.path).unwrap();
+            let expected = db
+                .count_articles(&ArticleQuery {
+                    filter: Some(&folder.filter),
+                    ..Default::default()
+                })
+                .unwrap();
+            assert_eq!(node.counts, expected, "{}", node.path);
+            check(db, folders, &node.children);
+        }
+    }
+    check(&db, &folders, &tree);
+
+    let travel = Expr::parse("life/travel").unwrap();
+    let newest = db
+        .query_articles(&ArticleQuery {
+            filter: Some(&travel),
+            ..Default::default()
+        })
+        .unwrap()
+        .iter()
+        .filter_map(|a| a.published_at)
+        .max();
+    assert!(newest.is_some());
+    assert_eq!(life.counts.newest, newest);
+    // The only AI article has no date
+    assert_eq!(tree[0].children[0].counts.newest, None);
+
+    let json = serde_json::to_value(&tree).unwrap();
+    assert_eq!(json[0]["path"], "Tech");
+    assert_eq!(json[0]["total"], 4);
+    assert_eq!(
+        json[0]["children"][0]["children"][0]["path"],
+        "Tech/AI/Unread"
+    );
+    assert!(json[1].get("filter").is_none());
+    assert!(json[2].get("children").is_none());
+}
+
+#[test]
+fn test_mute_hides_articles() {
+    let dir = TempDir::new().unwrap();
+    let db = sample_db(&dir);
+    let mute = |name: &str, filter: &str| Mute {
+        name: name.to_string(),
+        filter: Expr::parse(filter).unwrap(),
+        mark_read: false,
+    };
+    assert_eq!(mute_filter(&[]), None);
+    let mutes = [mute("Rust", "text~rust"), mute("Old", "age>=1w")];
+    let muted = mute_filter(&mutes).unwrap();
+    assert_eq!(muted.to_string(), "text~rust OR age>=1w");
+
+    let tech = Expr::parse("tech").unwrap();
+    let query = ArticleQuery {
+        filter: Some(&tech),
+        mute: Some(&muted),
+        ..Default::default()
+    };
+    let titles: Vec<String> = db
+        .query_articles(&query)
+        .unwrap()
+        .into_iter()
+        .map(|a| a.title)
+        .collect();
+    assert_eq!(
+        titles,
+        vec!["Technology roundup", "机器学习的未来发展与应用"]
+    );
+    let counts = db.count_articles(&query).unwrap();
+    assert_eq!((counts.total, counts.unread), (2, 1));
+
+    let tree = db.folder_counts(&nested_folders(), Some(&muted)).unwrap();
+    assert_eq!(tree[0].counts.total, 2);
+    assert_eq!(tree[2].path, "Deep Reads");
+    assert_eq!(tree[2].counts.total, 0);
+    assert_eq!(
+        db.folder_counts(&nested_folders(), None).unwrap()[0]
+            .counts
+            .total,
+        4
+    );
+}
+
+// ==================== Sort and grouping tests ====================
+
+fn sorted_ids(db: &Database, filter: Option<&Expr>, sort: SortOrder) -> Vec<i64> {
+    db.query_articles(&ArticleQuery {
+        filter,
+        sort,
+        ..Default::default()
+    })
+    .unwrap()
+    .iter()
+    .map(|a| a.id)
+    .collect()
+}
+
+#[test]
+fn test_sort_orders() {
+    let dir = TempDir::new().unwrap();
+    let db = sample_db(&dir);
+    assert_eq!(
+        sorted_ids(&db, None, SortOrder::Newest),
+        [7, 1, 5, 2, 3, 6, 4]
+    );
+    assert_eq!(
+        sorted_ids(&db, None, SortOrder::Oldest),
+        [6, 3, 2, 5, 1, 7, 4]
+    );
+    assert_eq!(
+        sorted_ids(&db, None, SortOrder::Shortest),
+        [6, 7, 5, 3, 2, 4, 1]
+    );
+    // "Peter Turchin" before "Ünïcode Blog", newest first within each
+    assert_eq!(
+        sorted_ids(&db, None, SortOrder::Source),
+        [1, 2, 6, 7, 5, 3, 4]
+    );
+}
+
+#[test]
+fn test_sort_by_score() {
+    let dir = TempDir::new().unwrap();
+    let db = sample_db(&dir);
+    let expr = Expr::parse("science OR tech OR long").unwrap();
+    // #1 has tech and long, #2 tech and science; then one tag each, newest first
+    assert_eq!(sorted_ids(&db, Some(&expr), SortOrder::Score), [1, 2, 5, 4]);
+    assert_eq!(
+        sorted_ids(&db, None, SortOrder::Score),
+        sorted_ids(&db, None, SortOrder::Newest)
+    );
+}
+
+#[test]
+fn test_group_articles() {
+    let dir = TempDir::new().unwrap();
+    let db = sample_db(&dir);
+    let articles = db.query_articles(&ArticleQuery::default()).unwrap();
+    let summary = |grouping| {
+        group_articles(&articles, grouping)
+            .into_iter()
+            .map(|(k, group)| (k, group.iter().map(|a| a.id).collect::<Vec<_>>()))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        summary(Grouping::None),
+        [(String::new(), vec![7, 1, 5, 2, 3, 6, 4])]
+    );
+    assert_eq!(
+        summary(Grouping::Source),
+        [
+            ("Ünïcode Blog".to_string(), vec![7, 5, 3, 4]),
+            ("Peter Turchin".to_string(), vec![1, 2, 6]),
+        ]
+    );
+    let by_day = summary(Grouping::Day);
+    assert_eq!(by_day.len(), 7);
+    assert_eq!(by_day[6], ("undated".to_string(), vec![4]));
+
+    let by_tag = summary(Grouping::Tag);
+    assert_eq!(by_tag[0], ("untagged".to_string(), vec![7]));
+    assert!(by_tag.contains(&("tech".to_string(), vec![2])));
+    assert!(by_tag.contains(&("long".to_string(), vec![1])));
+}
@@ -0,0 +1,181 @@
+use rusqlite::Connection;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tagrss::db::{ArticleQuery, Database, SchemaTooNew};
+use tagrss::folder::Expr;
+use tagrss::migrations::{latest_version, MIGRATIONS};
+use tempfile::TempDir;
+
+/// Databases as created by each release before schema versioning
+const FIXTURES: &[&str] = &[
+    "v0_baseline.sql",
+    "v0_manual_tags.sql",
+    "v0_classifier.sql",
+    "v0_article_tags.sql",
+];
+
+fn fixture_db(dir: &TempDir, fixture: &str) -> PathBuf {
+    let schema_dir = Path::new("tests/fixtures/schema");
+    let path = dir.path().join(format!("{}.db", fixture));
+    let conn = Connection::open(&path).unwrap();
+    conn.execute_batch(&fs::read_to_string(schema_dir.join(fixture)).unwrap())
+        .unwrap();
+    conn.execute_batch(&fs::read_to_string(schema_dir.join("data.sql")).unwrap())
+        .unwrap();
+    path
+}
+
+fn version(path: &Path) -> u32 {
+    Connection::open(path)
+        .unwrap()
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .unwrap()
+}
+
+#[test]
+fn test_migrations_are_ordered() {
+    for (i, m) in MIGRATIONS.iter().enumerate() {
+        assert_eq!(m.version as usize, i + 1, "{}", m.description);
+    }
+}
+
+#[test]
+fn test_new_database_is_latest() {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("new.db")).unwrap();
+    assert_eq!(db.schema_version().unwrap(), latest_version());
+    assert!(db.pending_migrations().unwrap().is_empty());
+    assert!(db.migrate().unwrap().is_empty());
+}
+
+#[test]
+fn test_upgrade_fixtures() {
+    for fixture in FIXTURES {
+        let dir = TempDir::new().unwrap();
+        let path = fixture_db(&dir, fixture);
+        assert_eq!(version(&path), 0);
+
+        let db = Database::open(&path).unwrap();
+        assert_eq!(
+            db.schema_version().unwrap(),
+            latest_version(),
+            "{}",
+            fixture
+        );
+
+        let articles = db.get_articles().unwrap();
+        assert_eq!(articles.len(), 3, "{}", fixture);
+        assert_eq!(db.get_sources().unwrap().len(), 2, "{}", fixture);
+        assert_eq!(db.get_rules().unwrap().len(), 1, "{}", fixture);
+
+        // Filters run on article_tags, which must have been filled
+        let tech = Expr::parse("tech").unwrap();
+        let ids: Vec<i64> = db
+            .query_articles(&ArticleQuery {
+                filter: Some(&tech),
+                ..Default::default()
+            })
+            .unwrap()
+            .iter()
+            .map(|a| a.id)
+            .collect();
+        assert_eq!(ids, vec![2], "{}", fixture);
+
+        // Tag join tables hold the JSON tags
+        let sources: Vec<i64> = db
+            .sources_with_tag("science")
+            .unwrap()
+            .iter()
+            .map(|s| s.id)
+            .collect();
+        assert_eq!(sources, vec![1], "{}", fixture);
+        let rust = db.articles_with_tag("tech/programming").unwrap();
+        assert_eq!(rust.len(), 1, "{}", fixture);
+        assert_eq!(rust[0].id, 2);
+
+        // Existing articles are in the search index, without markup
+        let hits = db
+            .search("borrow", &ArticleQuery::default(), ("[", "]"))
+            .unwrap();
+        assert_eq!(hits.len(), 1, "{}", fixture);
+        assert_eq!(hits[0].snippet, "The [borrow] checker");
+
+        // Tables added by later releases work
+        let manual: HashSet<String> = ["keep".to_string()].into();
+        db.set_manual_tags(1, &manual, false).unwrap();
+        assert!(db.get_manual_tags().unwrap().contains_key(&1));
+        assert!(db.load_classifier().unwrap().is_none());
+
+        // Rebuilt articles table lets a source go while keeping its articles
+        db.remove_source(1, true).unwrap();
+        db.remove_source(2, false).unwrap();
+        let kept = db.get_articles().unwrap();
+        assert_eq!(kept.len(), 1, "{}", fixture);
+        assert_eq!((kept[0].id, kept[0].source_id), (1, 0));
+        assert!(db.get_manual_tags().unwrap().contains_key(&1));
+    }
+}
+
+#[test]
+fn test_tag_lookups_use_index() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("plan.db");
+    Database::open(&path).unwrap();
+    let conn = Connection::open(&path).unwrap();
+    for (table, index) in [
+        ("article_tags", "idx_article_tags_tag"),
+        ("source_tags", "idx_source_tags_tag"),
+    ] {
+        let sql = format!(
+            "EXPLAIN QUERY PLAN SELECT * FROM {} WHERE tag = 'tech' OR (tag >= 'tech/' AND tag < 'tech0')",
+            table
+        );
+        let mut stmt = conn.prepare(&sql).unwrap();
+        let plan: Vec<String> = stmt
+            .query_map([], |row| row.get(3))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(
+            plan.iter().any(|step| step.contains(index)),
+            "{}: {:?}",
+            table,
+            plan
+        );
+    }
+}
+
+#[test]
+fn test_dry_run_leaves_database_untouched() {
+    let dir = TempDir::new().unwrap();
+    let path = fixture_db(&dir, "v0_baseline.sql");
+
+    let db = Database::open_unmigrated(&path).unwrap();
+    let pending = db.pending_migrations().unwrap();
+    assert_eq!(pending.len(), MIGRATIONS.len());
+    drop(db);
+    assert_eq!(version(&path), 0);
+
+    let db = Database::open_unmigrated(&path).unwrap();
+    let applied = db.migrate().unwrap();
+    assert_eq!(applied.len(), MIGRATIONS.len());
+    assert_eq!(version(&path), latest_version());
+}
+
+#[test]
+fn test_refuse_newer_schema() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("future.db");
+    Database::open(&path).unwrap();
+    Connection::open(&path)
+        .unwrap()
+        .pragma_update(None, "user_version", latest_version() + 1)
+        .unwrap();
+
+    let err = Database::open(&path).err().unwrap();
+    let too_new = err.downcast_ref::<SchemaTooNew>().unwrap();
+    assert_eq!(too_new.found, latest_version() + 1);
+    assert_eq!(too_new.supported, latest_version());
+    assert!(Database::open_unmigrated(&path).is_err());
+}
@@ -1,17 +1,22 @@
 use chrono::Utc;
+use std::collections::HashSet;
 use tagrss::models::{Article, Rule};
 
 fn make_article(tags: &[&str]) -> Article {
     Article {
         id: 1,
         source_id: 1,
+        source_title: String::new(),
         url: "http://example.com".to_string(),
         title: "Test Article".to_string(),
         content: None,
+        author: None,
         published_at: None,
         word_count: 100,
         tags: tags.iter().map(|s| s.to_string()).collect(),
+        manual_tags: HashSet::new(),
         read: false,
+        starred: false,
     }
 }
 
@@ -19,13 +24,17 @@ fn make_article_with_content(tags: &[&str], title: &str, content: &str, word_cou
     Article {
         id: 1,
         source_id: 1,
+        source_title: String::new(),
         url: "http://example.com".to_string(),
         title: title.to_string(),
         content: Some(content.to_string()),
+        author: None,
         published_at: Some(Utc::now()),
         word_count,
         tags: tags.iter().map(|s| s.to_string()).collect(),
+        manual_tags: HashSet::new(),
         read: false,
+        starred: false,
     }
 }
 
@@ -0,0 +1,211 @@
+use chrono::{Duration, Utc};
+use rusqlite::{params, Connection};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use tagrss::db::Database;
+use tagrss::folder::Expr;
+use tagrss::models::Article;
+use tagrss::retention::{Policy, Retention};
+use tempfile::TempDir;
+
+fn article(source_id: i64, url: &str, days_old: i64, tags: &[&str], read: bool) -> Article {
+    Article {
+        id: 0,
+        source_id,
+        source_title: String::new(),
+        url: url.to_string(),
+        title: url.to_string(),
+        content: None,
+        author: None,
+        published_at: Some(Utc::now() - Duration::days(days_old)),
+        word_count: 0,
+        tags: tags.iter().map(|t| t.to_string()).collect(),
+        manual_tags: HashSet::new(),
+        read,
+        starred: false,
+    }
+}
+
+/// Two sources with articles fetched as long ago as they were published
+fn retention_db(dir: &TempDir) -> (PathBuf, Database, i64, i64) {
+    let path = dir.path().join("test.db");
+    let db = Database::open(&path).unwrap();
+    let a = db
+        .add_source("http://a.example/feed", "A", &HashSet::new())
+        .unwrap();
+    let b = db
+        .add_source("http://b.example/feed", "B", &HashSet::new())
+        .unwrap();
+    db.add_articles(&[
+        article(a, "a-old-read", 40, &[], true),
+        article(a, "a-old-unread", 40, &[], false),
+        article(a, "a-old-important", 40, &["important"], true),
+        article(a, "a-new-read", 1, &[], true),
+        article(b, "b-1", 3, &["news"], false),
+        article(b, "b-2", 2, &["news"], false),
+        article(b, "b-3", 1, &["news"], false),
+    ])
+    .unwrap();
+    Connection::open(&path)
+        .unwrap()
+        .execute(
+            "UPDATE articles SET created_at = datetime(published_at)",
+            params![],
+        )
+        .unwrap();
+    (path, db, a, b)
+}
+
+fn policy(name: &str, filter: &str, max_days: Option<u32>, max_per_source: Option<u32>) -> Policy {
+    Policy {
+        name: name.to_string(),
+        filter: match filter {
+            "" => Expr::ALWAYS,
+            _ => Expr::parse(filter).unwrap(),
+        },
+        max_days,
+        max_per_source,
+    }
+}
+
+fn candidates(db: &Database, retention: &Retention) -> Vec<(String, String)> {
+    db.prune_candidates(retention)
+        .unwrap()
+        .into_iter()
+        .map(|c| (c.article.url, c.policy))
+        .collect()
+}
+
+fn pair(url: &str, policy: &str) -> (String, String) {
+    (url.to_string(), policy.to_string())
+}
+
+#[test]
+fn test_prune_candidates() {
+    let dir = TempDir::new().unwrap();
+    let (_path, db, _, _) = retention_db(&dir);
+
+    assert!(candidates(&db, &Retention::default()).is_empty());
+
+    let mut retention = Retention {
+        policies: vec![
+            policy("Read", "read", Some(30), None),
+            policy("Cap", "news", None, Some(2)),
+        ],
+        keep: Some(Expr::parse("important").unwrap()),
+        prune_after_sync: false,
+    };
+    // Oldest first, each with the first policy deleting it
+    assert_eq!(
+        candidates(&db, &retention),
+        vec![pair("a-old-read", "Read"), pair("b-1", "Cap")]
+    );
+
+    // Without `keep`, the important article goes too
+    retention.keep = None;
+    assert_eq!(candidates(&db, &retention).len(), 3);
+
+    // Caps count per source; kept articles do not count towards them
+    retention.policies = vec![policy("Cap", "", None, Some(1))];
+    retention.keep = Some(Expr::parse("important").unwrap());
+    let urls: Vec<String> = candidates(&db, &retention)
+        .into_iter()
+        .map(|(url, _)| url)
+        .collect();
+    assert_eq!(urls, vec!["a-old-read", "a-old-unread", "b-1", "b-2"]);
+
+    // Both limits in one policy
+    retention.policies = vec![policy("Both", "", Some(30), Some(2))];
+    let urls: Vec<String> = candidates(&db, &retention)
+        .into_iter()
+        .map(|(url, _)| url)
+        .collect();
+    assert_eq!(urls, vec!["a-old-read", "a-old-unread", "b-1"]);
+}
+
+#[test]
+fn test_recently_fetched_articles_are_not_old() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("test.db");
+    let db = Database::open(&path).unwrap();
+    let a = db
+        .add_source("http://a.example/feed", "A", &HashSet::new())
+        .unwrap();
+    // Published long ago but only just fetched
+    db.add_article(&article(a, "backfill", 400, &[], true))
+        .unwrap();
+    let retention = Retention {
+        policies: vec![policy("Read", "read", Some(30), None)],
+        ..Default::default()
+    };
+    assert!(candidates(&db, &retention).is_empty());
+}
+
+#[test]
+fn test_pruned_articles_are_not_fetched_again() {
+    let dir = TempDir::new().unwrap();
+    let (_path, db, a, _) = retention_db(&dir);
+    let manual: HashSet<String> = ["saved".to_string()].into();
+    let old = db.prune_candidates(&Retention {
+        policies: vec![policy("Old", "", Some(30), None)],
+        ..Default::default()
+    });
+    let ids: Vec<i64> = old.unwrap().iter().map(|c| c.article.id).collect();
+    assert_eq!(ids.len(), 3);
+    db.set_manual_tags(ids[0], &manual, false).unwrap();
+
+    assert_eq!(db.prune_articles(&ids).unwrap(), 3);
+    assert_eq!(db.get_articles().unwrap().len(), 4);
+    assert!(db.get_manual_tags().unwrap().is_empty());
+    assert!(db.articles_with_tag("important").unwrap().is_empty());
+    assert_eq!(db.prune_articles(&ids).unwrap(), 0);
+
+    // Sync sees the pruned URLs as known and cannot insert them
+    assert!(db.article_exists("a-old-read").unwrap());
+    assert_eq!(
+        db.add_articles(&[
+            article(a, "a-old-read", 40, &[], false),
+            article(a, "a-fresh", 0, &[], false),
+        ])
+        .unwrap(),
+        1
+    );
+    db.vacuum().unwrap();
+    assert_eq!(db.get_articles().unwrap().len(), 5);
+}
+
+#[test]
+fn test_starred_and_queued_articles_are_kept() {
+    let dir = TempDir::new().unwrap();
+    let (_path, db, _, _) = retention_db(&dir);
+    let retention = Retention {
+        policies: vec![policy("Old", "", Some(30), None)],
+        ..Default::default()
+    };
+    let ids: Vec<i64> = db
+        .prune_candidates(&retention)
+        .unwrap()
+        .iter()
+        .map(|c| c.article.id)
+        .collect();
+    assert_eq!(ids.len(), 3);
+
+    assert!(db.set_starred(ids[0], true).unwrap());
+    db.enqueue(ids[1], None).unwrap();
+    let urls: Vec<String> = candidates(&db, &retention)
+        .into_iter()
+        .map(|(url, _)| url)
+        .collect();
+    assert_eq!(urls, vec!["a-old-important"]);
+
+    // Starred articles do not count towards a cap either
+    let capped = Retention {
+        policies: vec![policy("Cap", "", None, Some(1))],
+        ..Default::default()
+    };
+    let urls: Vec<String> = candidates(&db, &capped)
+        .into_iter()
+        .map(|(url, _)| url)
+        .collect();
+    assert_eq!(urls, vec!["a-old-important", "b-1", "b-2"]);
+}
@@ -0,0 +1,175 @@
+use chrono::{Duration, Utc};
+use std::collections::HashSet;
+use tagrss::db::{ArticleQuery, Database};
+use tagrss::folder::Expr;
+use tagrss::models::Article;
+use tempfile::TempDir;
+
+fn search_db(dir: &TempDir) -> Database {
+    let db = Database::open(dir.path().join("test.db")).unwrap();
+    let source = db
+        .add_source("http://a.example/feed", "Example", &HashSet::new())
+        .unwrap();
+    let samples = [
+        (
+            "Rust ownership explained",
+            "<p>The <strong>borrow checker</strong> keeps memory safe.</p>",
+            Some("Jane Doe"),
+            &["tech/programming/rust"][..],
+            false,
+        ),
+        (
+            "Weekly links",
+            "<p>A checker for borrow rules, and some Python news.</p>",
+            None,
+            &["tech"],
+            true,
+        ),
+        (
+            "Cliodynamics and history",
+            "<p>Structural-demographic theory</p>",
+            Some("Peter Turchin"),
+            &["science"],
+            false,
+        ),
+        (
+            "Été à Paris",
+            "<p>La cuisine française</p>",
+            None,
+            &["life/travel"],
+            false,
+        ),
+    ];
+    for (i, (title, content, author, tags, read)) in samples.into_iter().enumerate() {
+        db.add_article(&Article {
+            id: 0,
+            source_id: source,
+            source_title: String::new(),
+            url: format!("http://a.example/{}", i),
+            title: title.to_string(),
+            content: Some(content.to_string()),
+            author: author.map(str::to_string),
+            published_at: Some(Utc::now() - Duration::days(i as i64)),
+            word_count: 100,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            manual_tags: HashSet::new(),
+            read,
+            starred: false,
+        })
+        .unwrap();
+    }
+    db
+}
+
+fn titles(db: &Database, text: &str, query: &ArticleQuery) -> Vec<String> {
+    db.search(text, query, ("[", "]"))
+        .unwrap()
+        .into_iter()
+        .map(|hit| hit.article.title)
+        .collect()
+}
+
+#[test]
+fn test_search_syntax() {
+    let dir = TempDir::new().unwrap();
+    let db = search_db(&dir);
+    let all = ArticleQuery::default();
+
+    // Title matches rank above content matches
+    assert_eq!(
+        titles(&db, "rust OR python", &all),
+        vec!["Rust ownership explained", "Weekly links"]
+    );
+    assert_eq!(
+        titles(&db, "\"borrow checker\"", &all),
+        vec!["Rust ownership explained"]
+    );
+    assert_eq!(titles(&db, "borrow checker", &all).len(), 2);
+    assert_eq!(
+        titles(&db, "borrow NOT python", &all),
+        vec!["Rust ownership explained"]
+    );
+    assert_eq!(titles(&db, "clio*", &all), vec!["Cliodynamics and history"]);
+    assert_eq!(
+        titles(&db, "turchin", &all),
+        vec!["Cliodynamics and history"]
+    );
+    // Diacritics are folded
+    assert_eq!(titles(&db, "ete francaise", &all), vec!["Été à Paris"]);
+    // Markup is not indexed
+    assert!(titles(&db, "strong", &all).is_empty());
+
+    assert!(db.search("\"unclosed", &all, ("[", "]")).is_err());
+}
+
+#[test]
+fn test_search_snippets() {
+    let dir = TempDir::new().unwrap();
+    let db = search_db(&dir);
+    let hits = db
+        .search("borrow", &ArticleQuery::default(), ("<<", ">>"))
+        .unwrap();
+    assert_eq!(hits.len(), 2);
+    for hit in &hits {
+        assert!(hit.snippet.contains("<<borrow>>"), "{}", hit.snippet);
+        assert!(!hit.snippet.contains("<p>"), "{}", hit.snippet);
+    }
+}
+
+#[test]
+fn test_search_with_filters() {
+    let dir = TempDir::new().unwrap();
+    let db = search_db(&dir);
+    let tech = Expr::parse("tech").unwrap();
+    let rust = Expr::parse("tech/programming/rust").unwrap();
+
+    let in_tech = ArticleQuery {
+        filter: Some(&tech),
+        ..Default::default()
+    };
+    assert_eq!(titles(&db, "borrow", &in_tech).len(), 2);
+    assert_eq!(
+        titles(
+            &db,
+            "borrow",
+            &ArticleQuery {
+                unread: true,
+                ..in_tech
+            }
+        ),
+        vec!["Rust ownership explained"]
+    );
+    assert_eq!(
+        titles(
+            &db,
+            "borrow",
+            &ArticleQuery {
+                mute: Some(&rust),
+                ..in_tech
+            }
+        ),
+        vec!["Weekly links"]
+    );
+    assert_eq!(
+        titles(
+            &db,
+            "borrow",
+            &ArticleQuery {
+                limit: Some(1),
+                ..in_tech
+            }
+        )
+        .len(),
+        1
+    );
+    let science = Expr::parse("science").unwrap();
+    assert!(titles(
+        &db,
+        "borrow",
+        &ArticleQuery {
+            filter: Some(&science),
+            ..Default::default()
+        }
+    )
+    .is_empty());
+}
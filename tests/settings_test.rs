@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tagrss::settings::{Settings, SettingsSource};
+use tempfile::TempDir;
+
+/// Resolve settings with only the given environment variables set
+fn resolve(flag: Option<&Path>, vars: &[(&str, &Path)], cwd: &Path) -> anyhow::Result<Settings> {
+    let vars: HashMap<String, String> = vars
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string_lossy().into_owned()))
+        .collect();
+    Settings::resolve_with(flag, &|key| vars.get(key).cloned(), cwd)
+}
+
+#[test]
+fn test_defaults_use_xdg_dirs() {
+    let dir = TempDir::new().unwrap();
+    let home = dir.path().join("home");
+    let cwd = dir.path().join("work");
+    fs::create_dir_all(&cwd).unwrap();
+
+    let settings = resolve(None, &[("HOME", &home)], &cwd).unwrap();
+    assert_eq!(settings.source, SettingsSource::Defaults);
+    assert_eq!(
+        settings.paths.db,
+        home.join(".local/share/tagrss/tagrss.db")
+    );
+    assert_eq!(
+        settings.paths.folders,
+        home.join(".config/tagrss/folders.yaml")
+    );
+    assert_eq!(settings.http.timeout_secs, 30);
+    assert_eq!(settings.sync.min_interval_minutes, 0);
+
+    // XDG variables override the home fallbacks, but relative ones are ignored
+    let data = dir.path().join("data");
+    let settings = resolve(
+        None,
+        &[
+            ("HOME", &home),
+            ("XDG_DATA_HOME", &data),
+            ("XDG_CONFIG_HOME", Path::new("relative")),
+        ],
+        &cwd,
+    )
+    .unwrap();
+    assert_eq!(settings.paths.db, data.join("tagrss/tagrss.db"));
+    assert_eq!(settings.paths.rules, home.join(".config/tagrss/rules.yaml"));
+}
+
+#[test]
+fn test_legacy_current_dir_layout() {
+    let dir = TempDir::new().unwrap();
+    let home = dir.path().join("home");
+    fs::write(dir.path().join("tagrss.db"), "").unwrap();
+
+    let settings = resolve(None, &[("HOME", &home)], dir.path()).unwrap();
+    assert_eq!(settings.source, SettingsSource::CurrentDir);
+    assert_eq!(settings.paths.db, dir.path().join("tagrss.db"));
+    assert_eq!(settings.paths.feeds, dir.path().join("configs/feeds.opml"));
+
+    // A checkout with only configs/ keeps using it, and the database next to it
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("configs")).unwrap();
+    let settings = resolve(None, &[("HOME", &home)], dir.path()).unwrap();
+    assert_eq!(settings.source, SettingsSource::CurrentDir);
+    assert_eq!(settings.paths.db, dir.path().join("tagrss.db"));
+    assert_eq!(settings.paths.rules, dir.path().join("configs/rules.yaml"));
+    assert!(settings.source.to_string().contains("configs/"));
+
+    // A file named configs is not a config directory
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("configs"), "").unwrap();
+    let settings = resolve(None, &[("HOME", &home)], dir.path()).unwrap();
+    assert_eq!(settings.source, SettingsSource::Defaults);
+}
+
+#[test]
+fn test_config_file_lookup_order() {
+    let dir = TempDir::new().unwrap();
+    let home = dir.path().join("home");
+    let xdg = home.join(".config/tagrss");
+    fs::create_dir_all(&xdg).unwrap();
+    fs::write(
+        xdg.join("tagrss.toml"),
+        "[sync]\nmin_interval_minutes = 1\n",
+    )
+    .unwrap();
+    let env_file = dir.path().join("env.toml");
+    fs::write(&env_file, "[sync]\nmin_interval_minutes = 2\n").unwrap();
+    let flag_file = dir.path().join("flag.toml");
+    fs::write(&flag_file, "[sync]\nmin_interval_minutes = 3\n").unwrap();
+    // A tagrss.db in the current directory loses to any config file
+    fs::write(dir.path().join("tagrss.db"), "").unwrap();
+
+    let settings = resolve(None, &[("HOME", &home)], dir.path()).unwrap();
+    assert_eq!(
+        settings.source,
+        SettingsSource::Xdg(xdg.join("tagrss.toml"))
+    );
+    assert_eq!(settings.sync.min_interval_minutes, 1);
+
+    let vars = [("HOME", home.as_path()), ("TAGRSS_CONFIG", &env_file)];
+    let settings = resolve(None, &vars, dir.path()).unwrap();
+    assert_eq!(settings.source, SettingsSource::Env(env_file.clone()));
+    assert_eq!(settings.sync.min_interval_minutes, 2);
+
+    let settings = resolve(Some(Path::new("flag.toml")), &vars, dir.path()).unwrap();
+    assert_eq!(settings.source, SettingsSource::Flag(flag_file));
+    assert_eq!(settings.sync.min_interval_minutes, 3);
+
+    // A file named explicitly must exist
+    let err = resolve(Some(Path::new("missing.toml")), &vars, dir.path()).unwrap_err();
+    assert!(format!("{:#}", err).contains("missing.toml"));
+}
+
+#[test]
+fn test_config_file_paths_and_http() {
+    let dir = TempDir::new().unwrap();
+    let home = dir.path().join("home");
+    let conf = dir.path().join("conf");
+    fs::create_dir_all(&conf).unwrap();
+    fs::write(
+        conf.join("tagrss.toml"),
+        r#"
+[paths]
+db = "~/feeds/tagrss.db"
+rules = "rules/main.yaml"
+folders = "/etc/tagrss/folders.yaml"
+
+[http]
+timeout_secs = 5
+proxy = "http://localhost:3128"
+"#,
+    )
+    .unwrap();
+
+    let flag = conf.join("tagrss.toml");
+    let settings = resolve(Some(&flag), &[("HOME", &home)], dir.path()).unwrap();
+    assert_eq!(settings.paths.db, home.join("feeds/tagrss.db"));
+    assert_eq!(settings.paths.rules, conf.join("rules/main.yaml"));
+    assert_eq!(
+        settings.paths.folders,
+        Path::new("/etc/tagrss/folders.yaml")
+    );
+    // Unset files sit next to the config file
+    assert_eq!(settings.paths.feeds, conf.join("feeds.opml"));
+    assert_eq!(settings.http.timeout_secs, 5);
+    assert!(settings.http.user_agent.starts_with("tagrss/"));
+    assert!(settings.http.client().is_ok());
+
+    let shown = settings.to_toml().unwrap();
+    assert!(shown.contains("[paths]"));
+    assert!(shown.contains("timeout_secs = 5"));
+    assert!(shown.contains("min_interval_minutes = 0"));
+}
+
+#[test]
+fn test_config_file_rejects_unknown_keys() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("tagrss.toml");
+    fs::write(&file, "[paths]\ndatabase = \"x.db\"\n").unwrap();
+
+    let err = resolve(Some(&file), &[], dir.path()).unwrap_err();
+    assert!(format!("{:#}", err).contains("database"));
+}
@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+use tagrss::suggest::{article_terms, keywords, match_tags, new_keywords, Keyword};
+use tagrss::text::tokenize;
+
+fn set(tags: &[&str]) -> HashSet<String> {
+    tags.iter().map(|s| s.to_string()).collect()
+}
+
+fn keyword(term: &str, score: f64) -> Keyword {
+    Keyword {
+        term: term.to_string(),
+        score,
+    }
+}
+
+#[test]
+fn test_keywords_prefer_rare_terms() {
+    let corpus = vec![
+        tokenize("rust compiler release notes"),
+        tokenize("release notes for python"),
+        tokenize("release notes for golang"),
+    ];
+    let top = keywords(&corpus[..1], &corpus, 2);
+    assert_eq!(top.len(), 2);
+    assert_eq!(top[0].term, "compiler");
+    assert_eq!(top[1].term, "rust");
+}
+
+#[test]
+fn test_keywords_empty() {
+    assert!(keywords(&[], &[], 5).is_empty());
+}
+
+#[test]
+fn test_match_tags() {
+    let kws = vec![keyword("rust", 0.5), keyword("learning", 0.2)];
+    let matched = match_tags(
+        &kws,
+        &set(&["tech/programming/rust", "machine-learning", "news"]),
+    );
+    assert_eq!(matched.len(), 2);
+    assert_eq!(matched[0].tag, "tech/programming/rust");
+    assert_eq!(matched[1].tag, "machine-learning");
+    assert!((matched[1].score - 0.1).abs() < 1e-9);
+}
+
+#[test]
+fn test_match_tags_short_tags() {
+    let kws = vec![keyword("ai", 0.4), keyword("rust", 0.5)];
+    let matched = match_tags(&kws, &set(&["tech/ai", "lang/rust", "ml"]));
+    let tags: Vec<&str> = matched.iter().map(|m| m.tag.as_str()).collect();
+    assert_eq!(tags, ["lang/rust", "tech/ai"]);
+
+    let kws = vec![keyword("ml", 0.3)];
+    assert!(new_keywords(&kws, &set(&["ml"])).is_empty());
+}
+
+#[test]
+fn test_new_keywords() {
+    let kws = vec![keyword("rust", 0.5), keyword("borrow", 0.3)];
+    let new = new_keywords(&kws, &set(&["tech/rust"]));
+    assert_eq!(new.len(), 1);
+    assert_eq!(new[0].term, "borrow");
+}
+
+#[test]
+fn test_suggest_short_tag_from_article_text() {
+    let articles = [
+        (
+            "AI models in 2026",
+            Some("<p>How AI is changing search. AI everywhere.</p>"),
+        ),
+        ("Rust release notes", Some("<p>The compiler is faster.</p>")),
+        ("Go release notes", Some("<p>The compiler is smaller.</p>")),
+    ];
+    let corpus: Vec<_> = articles
+        .iter()
+        .map(|(title, content)| article_terms(title, *content))
+        .collect();
+    assert!(corpus[0].contains(&"ai".to_string()));
+    assert!(!corpus[0].contains(&"is".to_string()));
+
+    let top = keywords(&corpus[..1], &corpus, 5);
+    let matched = match_tags(&top, &set(&["tech/ai", "lang/go", "news"]));
+    assert_eq!(matched[0].tag, "tech/ai");
+    assert!(matched.iter().all(|m| m.tag != "lang/go"));
+}
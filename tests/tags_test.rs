@@ -0,0 +1,387 @@
+use std::collections::HashSet;
+use std::fs;
+use tagrss::config::{rewrite_folders_file, rewrite_rules_file};
+use tagrss::db::{ArticleQuery, Database};
+use tagrss::folder::{rewrite_filter_tags, Expr};
+use tagrss::models::{Article, ManualTags, Rule};
+use tagrss::tags::{build_tree, glob_match, glob_prefix, is_under, TagCount, TagEdit};
+use tempfile::TempDir;
+
+fn set(tags: &[&str]) -> HashSet<String> {
+    tags.iter().map(|s| s.to_string()).collect()
+}
+
+fn rename(from: &str, to: &str) -> TagEdit {
+    TagEdit::Rename {
+        from: from.to_string(),
+        to: to.to_string(),
+    }
+}
+
+// ==================== TagEdit tests ====================
+
+#[test]
+fn test_is_under() {
+    assert!(is_under("tech", "tech"));
+    assert!(is_under("tech/ai/llm", "tech"));
+    assert!(is_under("tech/ai/llm", "tech/ai"));
+    assert!(!is_under("technology", "tech"));
+    assert!(!is_under("ai", "tech/ai"));
+}
+
+#[test]
+fn test_rename_moves_children() {
+    let edit = rename("tech/ai", "ai");
+    let tags = edit.apply(&set(&["tech/ai", "tech/ai/llm", "tech/web", "news"]));
+    assert_eq!(tags, set(&["ai", "ai/llm", "tech/web", "news"]));
+}
+
+#[test]
+fn test_rename_merges_collisions() {
+    let edit = rename("ml", "ai");
+    let tags = edit.apply(&set(&["ml", "ai"]));
+    assert_eq!(tags, set(&["ai"]));
+}
+
+#[test]
+fn test_delete_removes_children() {
+    let edit = TagEdit::Delete {
+        tag: "tech".to_string(),
+    };
+    let tags = edit.apply(&set(&["tech", "tech/ai", "technology"]));
+    assert_eq!(tags, set(&["technology"]));
+}
+
+// ==================== Database tests ====================
+
+#[test]
+fn test_db_rewrite_tags() {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("test.db")).unwrap();
+    let source_id = db
+        .add_source("http://example.com/feed", "Example", &set(&["tech/ai"]))
+        .unwrap();
+    db.add_article(&Article {
+        id: 0,
+        source_id,
+        source_title: String::new(),
+        url: "http://example.com/1".to_string(),
+        title: "One".to_string(),
+        content: None,
+        author: None,
+        published_at: None,
+        word_count: 0,
+        tags: set(&["tech/ai/llm", "news"]),
+        manual_tags: HashSet::new(),
+        read: false,
+        starred: false,
+    })
+    .unwrap();
+    db.add_rule(&Rule::WordCount {
+        min: Some(10),
+        max: None,
+        tag: "tech/ai/long".to_string(),
+    })
+    .unwrap();
+
+    let report = db.rewrite_tags(&[rename("tech/ai", "ai")]).unwrap();
+    assert_eq!(report.sources, 1);
+    assert_eq!(report.articles, 1);
+    assert_eq!(report.rules, 1);
+
+    assert_eq!(db.get_sources().unwrap()[0].tags, set(&["ai"]));
+    assert_eq!(db.get_articles().unwrap()[0].tags, set(&["ai/llm", "news"]));
+    assert_eq!(db.get_rules().unwrap()[0].1.tag(), "ai/long");
+    assert_eq!(db.all_tags().unwrap(), set(&["ai", "ai/llm", "news"]));
+    assert!(report.kept_rules.is_empty());
+
+    // A rule adding a deleted tag is kept, and reported
+    let report = db
+        .rewrite_tags(&[TagEdit::Delete {
+            tag: "ai".to_string(),
+        }])
+        .unwrap();
+    assert_eq!(report.articles, 1);
+    let rules = db.get_rules().unwrap();
+    assert_eq!(report.kept_rules, vec![rules[0].0]);
+    assert_eq!(rules[0].1.tag(), "ai/long");
+}
+
+// ==================== Config rewrite tests ====================
+
+#[test]
+fn test_rewrite_filter_tags() {
+    let out = rewrite_filter_tags("(tech/ai OR news) AND NOT tech/ai/llm", |t| {
+        rename("tech/ai", "ai").rewrite(t).unwrap()
+    });
+    assert_eq!(out, "(ai OR news) AND NOT ai/llm");
+
+    // Globs follow renames of the segments before their first wildcard
+    let rewrite = |input: &str, edit: TagEdit| {
+        rewrite_filter_tags(input, |t| edit.rewrite(t).unwrap_or(t.to_string()))
+    };
+    assert_eq!(
+        rewrite("tech/ai/* OR =tech/ai/*/x", rename("tech/ai", "ai")),
+        "ai/* OR =ai/*/x"
+    );
+    assert_eq!(
+        rewrite("tech/*/llm AND news", rename("tech", "my tech")),
+        "\"my tech/\"*\"/llm\" AND news"
+    );
+    assert_eq!(
+        Expr::parse(&rewrite("tech/*/llm", rename("tech", "my tech"))).unwrap(),
+        Expr::Glob {
+            pattern: "my tech/*/llm".to_string(),
+            exact: false
+        }
+    );
+    // Only whole leading segments are renamed
+    assert_eq!(
+        rewrite("tech/ai* OR */ai", rename("tech/ai", "ai")),
+        "tech/ai* OR */ai"
+    );
+
+    let expr = Expr::parse("tech/ai/* OR */rust OR news").unwrap();
+    assert_eq!(expr.tags(), vec!["tech/ai", "news"]);
+}
+
+#[test]
+fn test_rewrite_config_files_keep_comments() {
+    let dir = TempDir::new().unwrap();
+    let rules = dir.path().join("rules.yaml");
+    let folders = dir.path().join("folders.yaml");
+    fs::write(
+        &rules,
+        "# rules\nrules:\n  - type: contains\n    pattern: \"GPT\"\n    tag: tech/ai/llm # llm\n  - type: word_count\n    min: 3000\n    tag: long\n",
+    )
+    .unwrap();
+    fs::write(
+        &folders,
+        "folders:\n  # AI\n  - name: AI News\n    filter: \"tech/ai AND NOT long\"\n",
+    )
+    .unwrap();
+
+    let edits = [rename("tech/ai", "ai")];
+    assert_eq!(rewrite_rules_file(&rules, &edits).unwrap(), 1);
+    assert_eq!(rewrite_folders_file(&folders, &edits).unwrap(), 1);
+
+    assert_eq!(
+        fs::read_to_string(&rules).unwrap(),
+        "# rules\nrules:\n  - type: contains\n    pattern: \"GPT\"\n    tag: ai/llm # llm\n  - type: word_count\n    min: 3000\n    tag: long\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&folders).unwrap(),
+        "folders:\n  # AI\n  - name: AI News\n    filter: \"ai AND NOT long\"\n"
+    );
+}
+
+// ==================== Tag tree tests ====================
+
+fn article(url: &str, tags: &[&str], read: bool) -> Article {
+    Article {
+        id: 0,
+        source_id: 1,
+        source_title: String::new(),
+        url: url.to_string(),
+        title: url.to_string(),
+        content: None,
+        author: None,
+        published_at: None,
+        word_count: 0,
+        tags: set(tags),
+        manual_tags: HashSet::new(),
+        read,
+        starred: false,
+    }
+}
+
+#[test]
+fn test_db_tag_counts_include_descendants() {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("test.db")).unwrap();
+    db.add_source("http://example.com/feed", "Example", &set(&[]))
+        .unwrap();
+    db.add_article(&article("a", &["tech/ai", "tech/ai/llm"], false))
+        .unwrap();
+    db.add_article(&article("b", &["tech/web"], true)).unwrap();
+    db.add_article(&article("c", &["news"], false)).unwrap();
+
+    let counts = db.tag_counts(&ArticleQuery::default()).unwrap();
+    let get = |tag: &str| {
+        counts
+            .iter()
+            .find(|c| c.tag == tag)
+            .map(|c| (c.total, c.unread))
+    };
+    assert_eq!(get("tech"), Some((2, 1)));
+    assert_eq!(get("tech/ai"), Some((1, 1)));
+    assert_eq!(get("tech/ai/llm"), Some((1, 1)));
+    assert_eq!(get("tech/web"), Some((1, 0)));
+    assert_eq!(get("news"), Some((1, 1)));
+
+    let news = Expr::parse("NOT tech").unwrap();
+    let counts = db
+        .tag_counts(&ArticleQuery {
+            filter: Some(&news),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(counts.len(), 1);
+    assert_eq!(counts[0].tag, "news");
+
+    // Muted and read articles are left out like in every other query
+    let counts = db
+        .tag_counts(&ArticleQuery {
+            mute: Some(&Expr::parse("news").unwrap()),
+            unread: true,
+            ..Default::default()
+        })
+        .unwrap();
+    let tags: Vec<&str> = counts.iter().map(|c| c.tag.as_str()).collect();
+    assert_eq!(tags, ["tech", "tech/ai", "tech/ai/llm"]);
+}
+
+#[test]
+fn test_db_tag_lookups() {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("test.db")).unwrap();
+    let tech = db
+        .add_source("http://a.example/feed", "A", &set(&["tech/web"]))
+        .unwrap();
+    let other = db
+        .add_source("http://b.example/feed", "B", &set(&["technology"]))
+        .unwrap();
+    db.add_article(&article("a", &["tech/ai/llm"], false))
+        .unwrap();
+    db.add_article(&article("b", &["tech"], true)).unwrap();
+    db.add_article(&article("c", &["technology", "news"], false))
+        .unwrap();
+
+    let urls = |tag: &str| {
+        let mut urls: Vec<String> = db
+            .articles_with_tag(tag)
+            .unwrap()
+            .into_iter()
+            .map(|a| a.url)
+            .collect();
+        urls.sort();
+        urls
+    };
+    assert_eq!(urls("tech"), vec!["a", "b"]);
+    assert_eq!(urls("tech/ai"), vec!["a"]);
+    assert_eq!(urls("technology"), vec!["c"]);
+    assert!(urls("tech/a").is_empty());
+
+    let ids = |tag: &str| -> Vec<i64> {
+        db.sources_with_tag(tag)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.id)
+            .collect()
+    };
+    assert_eq!(ids("tech"), vec![tech]);
+    // Join tables follow tag updates
+    db.update_source_tags(other, &set(&["tech/ai"])).unwrap();
+    assert_eq!(ids("tech"), vec![tech, other]);
+    assert!(ids("technology").is_empty());
+    db.update_article_tags(2, &set(&["life"])).unwrap();
+    assert_eq!(urls("tech"), vec!["a"]);
+
+    assert_eq!(
+        db.all_tags().unwrap(),
+        set(&[
+            "tech/web",
+            "tech/ai",
+            "tech/ai/llm",
+            "life",
+            "technology",
+            "news"
+        ])
+    );
+}
+
+#[test]
+fn test_build_tree() {
+    let count = |tag: &str, total, unread| TagCount {
+        tag: tag.to_string(),
+        total,
+        unread,
+    };
+    let tree = build_tree(&[
+        count("tech/ai/llm", 1, 1),
+        count("tech", 2, 1),
+        count("tech/ai", 1, 1),
+        count("news", 1, 0),
+    ]);
+    assert_eq!(tree.len(), 2);
+    assert_eq!(tree[0].name, "news");
+    let tech = &tree[1];
+    assert_eq!((tech.total, tech.unread), (2, 1));
+    assert_eq!(tech.children[0].tag, "tech/ai");
+    assert_eq!(tech.children[0].children[0].name, "llm");
+}
+
+// ==================== Manual tag tests ====================
+
+#[test]
+fn test_manual_tags_apply() {
+    let manual = ManualTags {
+        added: set(&["mine"]),
+        removed: set(&["noise"]),
+    };
+    let mut tags = set(&["tech", "noise"]);
+    manual.apply(&mut tags);
+    assert_eq!(tags, set(&["tech", "mine"]));
+}
+
+#[test]
+fn test_db_manual_tags() {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open(dir.path().join("test.db")).unwrap();
+    db.add_source("http://example.com/feed", "Example", &set(&[]))
+        .unwrap();
+    db.add_article(&article("a", &["tech/ai", "noise"], false))
+        .unwrap();
+
+    let tags = db
+        .set_manual_tags(1, &set(&["tech/ai/mine"]), false)
+        .unwrap();
+    assert_eq!(tags, set(&["tech/ai", "tech/ai/mine", "noise"]));
+    let tags = db.set_manual_tags(1, &set(&["noise"]), true).unwrap();
+    assert_eq!(tags, set(&["tech/ai", "tech/ai/mine"]));
+    assert!(db.set_manual_tags(42, &set(&["x"]), false).is_err());
+
+    let a = db.get_article(1).unwrap().unwrap();
+    assert_eq!(a.manual_tags, set(&["tech/ai/mine"]));
+
+    db.rewrite_tags(&[rename("tech/ai", "ai")]).unwrap();
+    let manual = db.get_manual_tags().unwrap();
+    assert_eq!(manual[&1].added, set(&["ai/mine"]));
+    assert_eq!(manual[&1].removed, set(&["noise"]));
+}
+
+// ==================== Glob tests ====================
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match("tech/*", "tech/ai", false));
+    assert!(!glob_match("tech/*", "tech/ai/llm", false));
+    assert!(glob_match("tech/*", "tech/ai/llm", true));
+    assert!(glob_match("*/rust", "lang/rust", false));
+    assert!(glob_match("**/rust", "rust", false));
+    assert!(glob_match("**/rust", "tech/programming/rust", false));
+    assert!(glob_match("tech/**", "tech", false));
+    assert!(glob_match("te*h/a*", "tech/ai", false));
+    assert!(glob_match("*a*b*", "xaab", false));
+    assert!(!glob_match("ab*ba", "aba", false));
+    assert!(!glob_match("*/rust", "rust", true));
+}
+
+#[test]
+fn test_glob_prefix() {
+    assert_eq!(glob_prefix("tech/ai/*"), ("tech/ai", "/*"));
+    assert_eq!(glob_prefix("tech/*/llm"), ("tech", "/*/llm"));
+    assert_eq!(glob_prefix("tech/ai*"), ("tech", "/ai*"));
+    assert_eq!(glob_prefix("*/rust"), ("", "*/rust"));
+    assert_eq!(glob_prefix("tech*"), ("", "tech*"));
+}